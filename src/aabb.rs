@@ -0,0 +1,66 @@
+use glam::DVec3;
+
+use crate::ray::Ray;
+
+/// Axis-aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: DVec3,
+    pub max: DVec3,
+}
+
+impl Aabb {
+    /// Box containing nothing; surrounding it with another box yields that box.
+    pub const EMPTY: Aabb = Aabb {
+        min: DVec3::INFINITY,
+        max: DVec3::NEG_INFINITY,
+    };
+
+    /// Box spanning the two corner points, which may be given in any order.
+    pub fn new(a: DVec3, b: DVec3) -> Aabb {
+        return Aabb {
+            min: a.min(b),
+            max: a.max(b),
+        };
+    }
+
+    pub fn surrounding(a: &Aabb, b: &Aabb) -> Aabb {
+        return Aabb {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max),
+        };
+    }
+
+    pub fn centroid(&self) -> DVec3 {
+        return 0.5 * (self.min + self.max);
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let d = self.max - self.min;
+        return 2.0 * (d.x * d.y + d.y * d.z + d.z * d.x);
+    }
+
+    /// Slab test: whether `ray` passes through the box somewhere in (t_min, t_max).
+    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        return self.span(ray, t_min, t_max).is_some();
+    }
+
+    /// Parameter interval in which `ray` is inside the box, limited to (t_min, t_max).
+    pub fn span(&self, ray: &Ray, mut t_min: f64, mut t_max: f64) -> Option<(f64, f64)> {
+        for axis in 0..3 {
+            let inv_d = 1.0 / ray.dir[axis];
+            let mut t0 = (self.min[axis] - ray.origin[axis]) * inv_d;
+            let mut t1 = (self.max[axis] - ray.origin[axis]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            // Strict so that flat boxes around planar primitives are still hit.
+            if t_max < t_min {
+                return None;
+            }
+        }
+        return Some((t_min, t_max));
+    }
+}
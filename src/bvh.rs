@@ -0,0 +1,253 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable, HittableList};
+use crate::ray::Ray;
+
+/// Number of buckets the centroids are binned into when evaluating split candidates.
+const SAH_BINS: usize = 12;
+
+/// Bounding volume hierarchy node. Children are either further nodes or, at the
+/// leaves, the scene objects themselves.
+pub struct BvhNode {
+    bbox: Aabb,
+    left: Box<dyn Hittable>,
+    right: Option<Box<dyn Hittable>>,
+}
+
+impl BvhNode {
+    /// Builds a hierarchy over `objects`, choosing each split with the surface area
+    /// heuristic. Panics if `objects` is empty.
+    pub fn new(objects: Vec<Box<dyn Hittable>>) -> BvhNode {
+        let mut objects: Vec<(Aabb, Box<dyn Hittable>)> = objects
+            .into_iter()
+            .map(|object| (object.bounding_box(), object))
+            .collect();
+        return BvhNode::build(&mut objects);
+    }
+
+    fn build(objects: &mut Vec<(Aabb, Box<dyn Hittable>)>) -> BvhNode {
+        assert!(!objects.is_empty(), "cannot build a BVH without objects");
+        let bbox = objects
+            .iter()
+            .fold(Aabb::EMPTY, |acc, (b, _)| Aabb::surrounding(&acc, b));
+
+        if objects.len() <= 2 {
+            let right = if objects.len() == 2 {
+                objects.pop().map(|(_, object)| object)
+            } else {
+                None
+            };
+            let (_, left) = objects.pop().unwrap();
+            return BvhNode { bbox, left, right };
+        }
+
+        let mut right_objects = split(objects);
+        let left = BvhNode::build(objects);
+        let right = BvhNode::build(&mut right_objects);
+        return BvhNode {
+            bbox,
+            left: Box::new(left),
+            right: Some(Box::new(right)),
+        };
+    }
+}
+
+/// Puts `objects` into a BVH where possible. Unbounded objects such as planes would
+/// make every surface area infinite, so they are kept in a plain list next to it.
+pub fn accelerate(objects: Vec<Box<dyn Hittable>>) -> Box<dyn Hittable> {
+    let (bounded, mut unbounded): (Vec<_>, Vec<_>) = objects.into_iter().partition(|object| {
+        let bbox = object.bounding_box();
+        bbox.min.is_finite() && bbox.max.is_finite()
+    });
+    if bounded.is_empty() {
+        return Box::new(HittableList { objects: unbounded });
+    }
+    let bvh = Box::new(BvhNode::new(bounded));
+    if unbounded.is_empty() {
+        return bvh;
+    }
+    unbounded.push(bvh);
+    return Box::new(HittableList { objects: unbounded });
+}
+
+/// Moves the objects on the far side of the cheapest split into the returned vector.
+fn split(objects: &mut Vec<(Aabb, Box<dyn Hittable>)>) -> Vec<(Aabb, Box<dyn Hittable>)> {
+    let centroid_bounds = objects.iter().fold(Aabb::EMPTY, |acc, (b, _)| {
+        Aabb::surrounding(&acc, &Aabb::new(b.centroid(), b.centroid()))
+    });
+    let bin_of = |b: &Aabb, axis: usize| {
+        let extent = centroid_bounds.max[axis] - centroid_bounds.min[axis];
+        let offset = (b.centroid()[axis] - centroid_bounds.min[axis]) / extent;
+        return ((offset * SAH_BINS as f64) as usize).min(SAH_BINS - 1);
+    };
+
+    let mut best: Option<(f64, usize, usize)> = None;
+    for axis in 0..3 {
+        if centroid_bounds.max[axis] - centroid_bounds.min[axis] <= 0.0 {
+            continue;
+        }
+
+        let mut bins = [(Aabb::EMPTY, 0usize); SAH_BINS];
+        for (b, _) in objects.iter() {
+            let bin = &mut bins[bin_of(b, axis)];
+            bin.0 = Aabb::surrounding(&bin.0, b);
+            bin.1 += 1;
+        }
+
+        // right_costs[k] is the cost of everything in bins k.. as one child.
+        let mut right_costs = [f64::INFINITY; SAH_BINS];
+        let mut right_box = Aabb::EMPTY;
+        let mut right_count = 0;
+        for k in (1..SAH_BINS).rev() {
+            right_box = Aabb::surrounding(&right_box, &bins[k].0);
+            right_count += bins[k].1;
+            if right_count > 0 {
+                right_costs[k] = right_box.surface_area() * right_count as f64;
+            }
+        }
+
+        let mut left_box = Aabb::EMPTY;
+        let mut left_count = 0;
+        for k in 1..SAH_BINS {
+            left_box = Aabb::surrounding(&left_box, &bins[k - 1].0);
+            left_count += bins[k - 1].1;
+            if left_count == 0 || left_count == objects.len() {
+                continue;
+            }
+            let cost = left_box.surface_area() * left_count as f64 + right_costs[k];
+            if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                best = Some((cost, axis, k));
+            }
+        }
+    }
+
+    if let Some((_, axis, k)) = best {
+        let (left, right): (Vec<_>, Vec<_>) =
+            objects.drain(..).partition(|(b, _)| bin_of(b, axis) < k);
+        *objects = left;
+        return right;
+    }
+
+    // All centroids coincide, no split separates anything: halve the list instead.
+    let half = objects.len() / 2;
+    return objects.split_off(half);
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        if !self.bbox.hit(ray, t_min, t_max) {
+            return false;
+        }
+
+        let hit_left = self.left.hit(ray, t_min, t_max, rec);
+        let Some(right) = &self.right else {
+            return hit_left;
+        };
+        let hit_right = right.hit(ray, t_min, if hit_left { rec.t } else { t_max }, rec);
+
+        return hit_left || hit_right;
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        return self.bbox;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use glam::DVec3;
+
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::plane::Plane;
+    use crate::random::{random_unit_vector, Rng};
+    use crate::sphere::Sphere;
+
+    fn record() -> HitRecord {
+        return HitRecord {
+            p: DVec3::ZERO,
+            normal: DVec3::ZERO,
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: false,
+            material: None,
+        };
+    }
+
+    /// Random spheres, a few sharing a center, plus an unbounded ground plane.
+    fn objects() -> Vec<Box<dyn Hittable>> {
+        let material = Arc::new(Lambertian::new(DVec3::splat(0.5)));
+        let mut rng = Rng::new(4);
+        let mut objects: Vec<Box<dyn Hittable>> = Vec::new();
+        for _ in 0..200 {
+            let center = DVec3::new(rng.next_f64(), rng.next_f64(), rng.next_f64()) * 10.0;
+            objects.push(Box::new(Sphere {
+                center,
+                radius: 0.1 + 0.5 * rng.next_f64(),
+                material: material.clone(),
+            }));
+        }
+        for radius in [0.2, 0.4, 0.6] {
+            objects.push(Box::new(Sphere {
+                center: DVec3::splat(5.0),
+                radius,
+                material: material.clone(),
+            }));
+        }
+        objects.push(Box::new(Plane {
+            point: DVec3::new(0.0, -1.0, 0.0),
+            normal: DVec3::Y,
+            material,
+        }));
+        return objects;
+    }
+
+    #[test]
+    fn accelerated_hits_match_brute_force() {
+        let list = HittableList { objects: objects() };
+        let accelerated = accelerate(objects());
+        let mut rng = Rng::new(8);
+        let mut hits = 0;
+        for _ in 0..5000 {
+            let ray = Ray {
+                origin: DVec3::new(rng.next_f64(), rng.next_f64(), rng.next_f64()) * 14.0
+                    - DVec3::splat(2.0),
+                dir: random_unit_vector(&mut rng),
+                time: 0.0,
+            };
+            let (mut expected, mut actual) = (record(), record());
+            let hit = list.hit(&ray, 0.001, f64::INFINITY, &mut expected);
+            assert_eq!(
+                accelerated.hit(&ray, 0.001, f64::INFINITY, &mut actual),
+                hit
+            );
+            if hit {
+                hits += 1;
+                assert_eq!(actual.t, expected.t);
+                assert_eq!(actual.normal, expected.normal);
+            }
+        }
+        // Both the plane and the spheres must have been exercised.
+        assert!(hits > 2500 && hits < 5000, "{}", hits);
+    }
+
+    #[test]
+    fn accelerate_handles_only_unbounded_or_only_bounded_objects() {
+        let ray = Ray {
+            origin: DVec3::new(5.0, 20.0, 5.0),
+            dir: DVec3::NEG_Y,
+            time: 0.0,
+        };
+        let (bounded, unbounded): (Vec<_>, Vec<_>) = objects()
+            .into_iter()
+            .partition(|object| object.bounding_box().min.is_finite());
+        let mut rec = record();
+        assert!(accelerate(bounded).hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        // The largest sphere at (5, 5, 5) is the furthest the ray can get.
+        assert!(rec.t <= 14.4 + 1e-9);
+        assert!(accelerate(unbounded).hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 21.0);
+    }
+}
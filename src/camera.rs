@@ -0,0 +1,227 @@
+use std::cmp::max;
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+
+use glam::{DVec2, DVec3};
+
+use crate::ray::Ray;
+
+/// User-facing camera parameters, turned into a [`Camera`] by [`Camera::new`].
+pub struct CameraSettings {
+    pub look_from: DVec3,
+    pub look_at: DVec3,
+    pub vup: DVec3,
+    /// Vertical field of view in degrees.
+    pub vfov: f64,
+    pub aspect_ratio: f64,
+    pub image_width: i32,
+    /// Overrides the height otherwise derived from the width and `aspect_ratio`.
+    pub image_height: Option<i32>,
+    /// Diameter of the lens; zero gives a pinhole camera with everything in focus.
+    pub aperture: f64,
+    /// Distance from `look_from` to the plane in perfect focus. Defaults to the
+    /// distance to `look_at`.
+    pub focus_distance: Option<f64>,
+    /// Interval of ray times; moving objects are blurred over this part of their
+    /// motion from time 0 to time 1.
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        return CameraSettings {
+            look_from: DVec3::new(0.0, 0.0, 0.0),
+            look_at: DVec3::new(0.0, 0.0, -1.0),
+            vup: DVec3::new(0.0, 1.0, 0.0),
+            vfov: 90.0,
+            aspect_ratio: 16.0 / 9.0,
+            image_width: 400,
+            image_height: None,
+            aperture: 0.0,
+            focus_distance: None,
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+        };
+    }
+}
+
+/// Thin-lens camera mapping image pixels to primary rays.
+pub struct Camera {
+    pub image_width: i32,
+    pub image_height: i32,
+    center: DVec3,
+    pixel00_loc: DVec3,
+    pixel_delta_u: DVec3,
+    pixel_delta_v: DVec3,
+    /// Horizontal and vertical lens radius vectors; zero for a pinhole camera.
+    defocus_disk_u: DVec3,
+    defocus_disk_v: DVec3,
+    shutter_open: f64,
+    shutter_close: f64,
+}
+
+impl Camera {
+    pub fn new(settings: CameraSettings) -> Camera {
+        let image_width = settings.image_width;
+        let image_height = settings
+            .image_height
+            .unwrap_or_else(|| max((image_width as f64 / settings.aspect_ratio) as i32, 1));
+
+        let center = settings.look_from;
+        // The viewport lies in the focus plane, so rays through it from anywhere on
+        // the lens meet there.
+        let focus_distance = settings
+            .focus_distance
+            .unwrap_or_else(|| (settings.look_from - settings.look_at).length());
+        let h = (settings.vfov.to_radians() / 2.0).tan();
+        let viewport_height = 2.0 * h * focus_distance;
+        let viewport_width = viewport_height * (image_width as f64 / image_height as f64);
+
+        // Orthonormal basis for the camera frame; w points away from the view direction.
+        let w = (settings.look_from - settings.look_at).normalize();
+        let u = settings.vup.cross(w).normalize();
+        let v = w.cross(u);
+
+        let viewport_u = viewport_width * u;
+        let viewport_v = viewport_height * -v;
+
+        let pixel_delta_u = viewport_u / image_width as f64;
+        let pixel_delta_v = viewport_v / image_height as f64;
+
+        let viewport_upper_left = center - focus_distance * w - viewport_u / 2.0 - viewport_v / 2.0;
+        let pixel00_loc = viewport_upper_left + 0.5 * (pixel_delta_u + pixel_delta_v);
+
+        let lens_radius = settings.aperture / 2.0;
+
+        return Camera {
+            image_width,
+            image_height,
+            center,
+            pixel00_loc,
+            pixel_delta_u,
+            pixel_delta_v,
+            defocus_disk_u: lens_radius * u,
+            defocus_disk_v: lens_radius * v,
+            shutter_open: settings.shutter_open,
+            shutter_close: settings.shutter_close,
+        };
+    }
+
+    /// Whether rays start from different points on a lens, in which case
+    /// [`Camera::get_ray`] makes use of its `lens` argument.
+    pub fn has_defocus(&self) -> bool {
+        return self.defocus_disk_u != DVec3::ZERO;
+    }
+
+    /// Ray through pixel (`i`, `j`), where `offset` in [0, 1)^2 selects the point
+    /// inside the pixel and (0.5, 0.5) is its center. `lens` in [0, 1)^2 likewise
+    /// selects the ray origin on the lens, and `shutter` in [0, 1) the moment
+    /// between the shutter opening and closing.
+    pub fn get_ray(&self, i: i32, j: i32, offset: DVec2, lens: DVec2, shutter: f64) -> Ray {
+        let pixel_sample = self.pixel00_loc
+            + ((i as f64 + offset.x - 0.5) * self.pixel_delta_u)
+            + ((j as f64 + offset.y - 0.5) * self.pixel_delta_v);
+        let disk = concentric_disk(lens);
+        let origin = self.center + disk.x * self.defocus_disk_u + disk.y * self.defocus_disk_v;
+        return Ray {
+            origin,
+            dir: pixel_sample - origin,
+            time: self.shutter_open + shutter * (self.shutter_close - self.shutter_open),
+        };
+    }
+}
+
+/// Maps the unit square onto the unit disk, keeping stratified samples evenly
+/// spread (Shirley and Chiu's concentric mapping).
+fn concentric_disk(p: DVec2) -> DVec2 {
+    let p = 2.0 * p - DVec2::ONE;
+    if p == DVec2::ZERO {
+        return DVec2::ZERO;
+    }
+    let (radius, theta) = if p.x.abs() > p.y.abs() {
+        (p.x, FRAC_PI_4 * (p.y / p.x))
+    } else {
+        (p.y, FRAC_PI_2 - FRAC_PI_4 * (p.x / p.y))
+    };
+    return radius * DVec2::new(theta.cos(), theta.sin());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> CameraSettings {
+        return CameraSettings {
+            look_from: DVec3::new(0.0, 0.0, 0.0),
+            look_at: DVec3::new(0.0, 0.0, -4.0),
+            aperture: 0.5,
+            image_width: 40,
+            aspect_ratio: 1.0,
+            ..CameraSettings::default()
+        };
+    }
+
+    #[test]
+    fn rays_through_a_pixel_meet_in_the_focus_plane() {
+        for focus_distance in [None, Some(2.0)] {
+            let camera = Camera::new(CameraSettings {
+                focus_distance,
+                ..settings()
+            });
+            assert!(camera.has_defocus());
+            let expected_z = -focus_distance.unwrap_or(4.0);
+            let offset = DVec2::new(0.3, 0.7);
+            let mut focus_points = Vec::new();
+            for lens in [
+                DVec2::new(0.1, 0.2),
+                DVec2::new(0.9, 0.5),
+                DVec2::new(0.4, 0.99),
+            ] {
+                let ray = camera.get_ray(7, 29, offset, lens, 0.0);
+                assert!(ray.origin.length() <= 0.25 + 1e-12);
+                assert!(ray.origin.z.abs() < 1e-12);
+                let t = expected_z / ray.dir.z;
+                focus_points.push(ray.at(t));
+            }
+            assert!((focus_points[0] - focus_points[1]).length() < 1e-9);
+            assert!((focus_points[0] - focus_points[2]).length() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn pinhole_ignores_lens_sample() {
+        let camera = Camera::new(CameraSettings {
+            aperture: 0.0,
+            ..settings()
+        });
+        assert!(!camera.has_defocus());
+        let a = camera.get_ray(3, 4, DVec2::splat(0.5), DVec2::new(0.1, 0.9), 0.0);
+        let b = camera.get_ray(3, 4, DVec2::splat(0.5), DVec2::new(0.8, 0.2), 0.0);
+        assert_eq!(a.origin, DVec3::ZERO);
+        assert_eq!(a.dir, b.dir);
+    }
+
+    #[test]
+    fn ray_times_cover_the_shutter_interval() {
+        let camera = Camera::new(CameraSettings {
+            shutter_open: 0.25,
+            shutter_close: 0.75,
+            ..settings()
+        });
+        let at = |shutter| camera.get_ray(0, 0, DVec2::ZERO, DVec2::ZERO, shutter).time;
+        assert_eq!(at(0.0), 0.25);
+        assert_eq!(at(0.5), 0.5);
+        assert!(at(0.999) < 0.75);
+    }
+
+    #[test]
+    fn concentric_mapping_stays_inside_disk() {
+        for i in 0..=10 {
+            for j in 0..=10 {
+                let p = concentric_disk(DVec2::new(i as f64 / 10.0, j as f64 / 10.0));
+                assert!(p.length() <= 1.0 + 1e-12, "{}", p);
+            }
+        }
+        assert!((concentric_disk(DVec2::new(1.0, 0.5)) - DVec2::X).length() < 1e-12);
+    }
+}
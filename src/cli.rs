@@ -0,0 +1,374 @@
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+use raytracer_rs::color::{Encoding, OutputTransform, ToneMap};
+use raytracer_rs::output::Format;
+use raytracer_rs::render::{MisHeuristic, Progressive};
+use raytracer_rs::sampler::PatternKind;
+
+pub const USAGE: &str = "\
+Usage: raytracer_rs [OPTIONS]
+
+Options:
+      --scene <PATH>       Scene description to render (default: built-in scene)
+      --output <PATH>      Output file, format chosen by extension: .ppm, .png, .hdr
+                           (default: ASCII PPM on stdout)
+      --width <PIXELS>     Image width, overrides the scene
+      --height <PIXELS>    Image height, overrides the scene
+      --spp <N>            Samples per pixel [default: 100]
+      --max-depth <N>      Maximum number of bounces per path [default: 50]
+      --seed <N>           Seed for all random numbers [default: 0]
+      --threads <N>        Worker threads [default: number of CPUs]
+      --sampler <NAME>     jittered, stratified, halton or sobol [default: stratified]
+      --mis <NAME>         Light sampling weights: balance or power [default: power]
+      --time-limit <SECONDS>
+                           Stop after this much time, even before reaching --spp
+      --checkpoint-passes <N>
+                           Save the image so far to --output every N passes
+      --checkpoint-seconds <SECONDS>
+                           Save the image so far to --output at this interval
+      --exposure <STOPS>   Exposure adjustment [default: 0]
+      --tone-map <NAME>    clamp, reinhard or aces [default: clamp]
+      --encoding <NAME>    linear, gamma2.2 or srgb [default: srgb]
+  -h, --help               Print this help
+
+With --time-limit or a checkpoint option, the image is rendered progressively: in
+passes of one sample per pixel that are averaged as they come in. Every sampler
+spreads the samples of a run stopped early by the time limit over the whole pixel;
+halton and sobol spread them the most evenly.
+";
+
+/// Largest accepted image width or height.
+const MAX_DIMENSION: i32 = 65_536;
+
+/// Largest accepted number of pixels, which keeps the framebuffer at about a gigabyte.
+const MAX_PIXELS: usize = 1 << 26;
+
+/// Largest accepted number of worker threads.
+const MAX_THREADS: usize = 1024;
+
+/// Largest accepted number of bounces. Every bounce recurses once, so this keeps
+/// paths in closed scenes within the stack of the worker threads.
+const MAX_DEPTH: u32 = 1000;
+
+/// Every option that takes a value.
+const FLAGS: [&str; 16] = [
+    "--scene",
+    "--output",
+    "--width",
+    "--height",
+    "--spp",
+    "--max-depth",
+    "--seed",
+    "--threads",
+    "--sampler",
+    "--mis",
+    "--time-limit",
+    "--checkpoint-passes",
+    "--checkpoint-seconds",
+    "--exposure",
+    "--tone-map",
+    "--encoding",
+];
+
+pub struct Options {
+    pub scene: Option<PathBuf>,
+    pub output: Option<PathBuf>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub samples_per_pixel: u32,
+    pub max_depth: u32,
+    pub seed: u64,
+    pub threads: usize,
+    pub sampler: PatternKind,
+    pub mis: MisHeuristic,
+    /// Set when any progressive rendering option is given.
+    pub progressive: Option<Progressive>,
+    pub transform: OutputTransform,
+}
+
+pub enum Command {
+    Render(Options),
+    Help,
+}
+
+/// Parses the program arguments, without the leading program name.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut options = Options {
+        scene: None,
+        output: None,
+        width: None,
+        height: None,
+        samples_per_pixel: 100,
+        max_depth: 50,
+        seed: 0,
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        sampler: PatternKind::Stratified,
+        mis: MisHeuristic::Power,
+        progressive: None,
+        transform: OutputTransform::default(),
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            return Ok(Command::Help);
+        }
+        // Accept both `--flag value` and `--flag=value`.
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        if !flag.starts_with("--") {
+            return Err(format!("unexpected argument '{}'", flag));
+        }
+        if !FLAGS.contains(&flag.as_str()) {
+            return Err(format!("unknown option '{}'", flag));
+        }
+        let value = match inline_value.or_else(|| args.next()) {
+            Some(value) => value,
+            None => return Err(format!("{} needs a value", flag)),
+        };
+
+        match flag.as_str() {
+            "--scene" => options.scene = Some(PathBuf::from(value)),
+            "--output" => {
+                let path = PathBuf::from(value);
+                if Format::from_path(&path).is_none() {
+                    return Err(format!(
+                        "unsupported output format '{}' (expected .ppm, .png or .hdr)",
+                        path.display()
+                    ));
+                }
+                options.output = Some(path);
+            }
+            "--width" => options.width = Some(bounded(&flag, &value, MAX_DIMENSION)?),
+            "--height" => options.height = Some(bounded(&flag, &value, MAX_DIMENSION)?),
+            "--spp" => options.samples_per_pixel = positive(&flag, &value)?,
+            "--max-depth" => options.max_depth = bounded(&flag, &value, MAX_DEPTH)?,
+            "--seed" => options.seed = parse(&flag, &value)?,
+            "--threads" => options.threads = bounded(&flag, &value, MAX_THREADS)?,
+            "--sampler" => options.sampler = parse(&flag, &value)?,
+            "--mis" => options.mis = parse(&flag, &value)?,
+            "--time-limit" => {
+                let progressive = options.progressive.get_or_insert_with(Progressive::default);
+                progressive.time_limit = Some(seconds(&flag, &value)?);
+            }
+            "--checkpoint-passes" => {
+                let progressive = options.progressive.get_or_insert_with(Progressive::default);
+                progressive.checkpoint_passes = Some(positive(&flag, &value)?);
+            }
+            "--checkpoint-seconds" => {
+                let progressive = options.progressive.get_or_insert_with(Progressive::default);
+                progressive.checkpoint_interval = Some(seconds(&flag, &value)?);
+            }
+            "--exposure" => {
+                let exposure: f64 = parse(&flag, &value)?;
+                if !exposure.is_finite() {
+                    return Err(format!("invalid value '{}' for {}", value, flag));
+                }
+                options.transform.exposure = exposure;
+            }
+            "--tone-map" => options.transform.tone_map = parse::<ToneMap>(&flag, &value)?,
+            "--encoding" => options.transform.encoding = parse::<Encoding>(&flag, &value)?,
+            _ => unreachable!("flag '{}' is listed in FLAGS but not handled", flag),
+        }
+    }
+    if let Some(progressive) = &options.progressive {
+        let checkpoints =
+            progressive.checkpoint_passes.is_some() || progressive.checkpoint_interval.is_some();
+        if checkpoints && options.output.is_none() {
+            return Err("checkpoints need an --output file".to_string());
+        }
+    }
+    return Ok(Command::Render(options));
+}
+
+fn parse<T: FromStr>(flag: &str, value: &str) -> Result<T, String>
+where
+    T::Err: Display,
+{
+    return value
+        .parse()
+        .map_err(|err| format!("invalid value '{}' for {}: {}", value, flag, err));
+}
+
+fn positive<T: FromStr + Default + PartialOrd>(flag: &str, value: &str) -> Result<T, String>
+where
+    T::Err: Display,
+{
+    let parsed: T = parse(flag, value)?;
+    if parsed <= T::default() {
+        return Err(format!("{} must be greater than zero", flag));
+    }
+    return Ok(parsed);
+}
+
+fn bounded<T: FromStr + Default + PartialOrd + Display>(
+    flag: &str,
+    value: &str,
+    max: T,
+) -> Result<T, String>
+where
+    T::Err: Display,
+{
+    let parsed: T = positive(flag, value)?;
+    if parsed > max {
+        return Err(format!("{} must be at most {}", flag, max));
+    }
+    return Ok(parsed);
+}
+
+/// Rejects images too large to allocate, whether their size came from the
+/// command line or the scene.
+pub fn check_image_size(width: i32, height: i32) -> Result<(), String> {
+    let pixels = (width.max(0) as usize).checked_mul(height.max(0) as usize);
+    if width > MAX_DIMENSION || height > MAX_DIMENSION || pixels.is_none_or(|n| n > MAX_PIXELS) {
+        return Err(format!(
+            "image size {}x{} is too large (at most {} pixels per side and {} in total)",
+            width, height, MAX_DIMENSION, MAX_PIXELS
+        ));
+    }
+    return Ok(());
+}
+
+fn seconds(flag: &str, value: &str) -> Result<Duration, String> {
+    let parsed: f64 = positive(flag, value)?;
+    return Duration::try_from_secs_f64(parsed)
+        .map_err(|err| format!("invalid value '{}' for {}: {}", value, flag, err));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(args: &[&str]) -> Result<Options, String> {
+        return match parse_args(args.iter().map(|arg| arg.to_string()))? {
+            Command::Render(options) => Ok(options),
+            Command::Help => Err("unexpected help".to_string()),
+        };
+    }
+
+    fn error(args: &[&str]) -> String {
+        return options(args).err().expect("arguments should be rejected");
+    }
+
+    #[test]
+    fn values_follow_the_flag_or_an_equals_sign() {
+        let options = options(&[
+            "--spp",
+            "8",
+            "--max-depth=12",
+            "--sampler",
+            "halton",
+            "--output=out.hdr",
+            "--exposure=-1.5",
+        ])
+        .unwrap();
+        assert_eq!(options.samples_per_pixel, 8);
+        assert_eq!(options.max_depth, 12);
+        assert_eq!(options.sampler, PatternKind::Halton);
+        assert_eq!(options.output, Some(PathBuf::from("out.hdr")));
+        assert_eq!(options.transform.exposure, -1.5);
+        assert!(options.progressive.is_none());
+        assert!(matches!(
+            parse_args(["--spp=8".to_string(), "-h".to_string()]),
+            Ok(Command::Help)
+        ));
+    }
+
+    #[test]
+    fn unknown_options_and_missing_values_are_reported() {
+        assert_eq!(error(&["--colour", "red"]), "unknown option '--colour'");
+        assert_eq!(error(&["--spp=4", "--seed"]), "--seed needs a value");
+        assert_eq!(error(&["scene.txt"]), "unexpected argument 'scene.txt'");
+        assert!(error(&["--sampler", "random"]).contains("unknown sample pattern"));
+        assert_eq!(
+            error(&["--output", "out.jpg"]),
+            "unsupported output format 'out.jpg' (expected .ppm, .png or .hdr)"
+        );
+        assert_eq!(
+            error(&["--checkpoint-passes", "10"]),
+            "checkpoints need an --output file"
+        );
+    }
+
+    #[test]
+    fn counts_must_be_positive_numbers() {
+        for flag in ["--spp", "--max-depth", "--width", "--height", "--threads"] {
+            assert_eq!(
+                error(&[flag, "0"]),
+                format!("{} must be greater than zero", flag)
+            );
+        }
+        assert_eq!(
+            error(&["--width", "-3"]),
+            "--width must be greater than zero"
+        );
+        assert!(error(&["--spp", "-3"]).starts_with("invalid value '-3' for --spp"));
+        assert!(error(&["--spp", "many"]).starts_with("invalid value 'many' for --spp"));
+        assert!(error(&["--exposure", "inf"]).starts_with("invalid value 'inf' for --exposure"));
+    }
+
+    #[test]
+    fn sizes_are_bounded() {
+        assert_eq!(options(&["--width=65536"]).unwrap().width, Some(65536));
+        assert_eq!(
+            error(&["--height", "65537"]),
+            "--height must be at most 65536"
+        );
+        assert_eq!(options(&["--threads=1024"]).unwrap().threads, 1024);
+        assert_eq!(
+            error(&["--threads", "1025"]),
+            "--threads must be at most 1024"
+        );
+        assert_eq!(options(&["--max-depth=1000"]).unwrap().max_depth, 1000);
+        assert_eq!(
+            error(&["--max-depth", "4000000000"]),
+            "--max-depth must be at most 1000"
+        );
+
+        assert!(check_image_size(8192, 8192).is_ok());
+        assert!(check_image_size(8192, 8193).is_err());
+        assert!(check_image_size(65537, 1).is_err());
+        // Would overflow a 32-bit pixel count.
+        assert!(check_image_size(i32::MAX, i32::MAX).is_err());
+    }
+
+    #[test]
+    fn durations_must_be_positive_and_representable() {
+        let progressive = options(&[
+            "--time-limit",
+            "1.5",
+            "--checkpoint-seconds=30",
+            "--output=out.png",
+        ])
+        .unwrap()
+        .progressive
+        .unwrap();
+        assert_eq!(progressive.time_limit, Some(Duration::from_millis(1500)));
+        assert_eq!(
+            progressive.checkpoint_interval,
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            error(&["--time-limit", "0"]),
+            "--time-limit must be greater than zero"
+        );
+        for value in ["1e30", "inf", "NaN"] {
+            assert!(
+                error(&["--time-limit", value])
+                    .starts_with(&format!("invalid value '{}' for --time-limit", value)),
+                "{}",
+                value
+            );
+        }
+        assert!(
+            error(&["--checkpoint-seconds", "1e300", "--output", "out.png"])
+                .starts_with("invalid value '1e300' for --checkpoint-seconds")
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use std::str::FromStr;
+
+use glam::DVec3;
+
+/// Maps scene-referred radiance into the displayable [0, 1] range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToneMap {
+    /// Values above 1.0 are simply clamped.
+    Clamp,
+    Reinhard,
+    /// Narkowicz's fit of the ACES filmic curve.
+    Aces,
+}
+
+/// Transfer function applied after tone mapping.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Linear,
+    Gamma22,
+    Srgb,
+}
+
+/// Output stage turning linear render values into display-encoded values in [0, 1].
+#[derive(Clone, Copy, Debug)]
+pub struct OutputTransform {
+    /// Exposure adjustment in stops; each stop doubles the brightness.
+    pub exposure: f64,
+    pub tone_map: ToneMap,
+    pub encoding: Encoding,
+}
+
+impl Default for OutputTransform {
+    fn default() -> Self {
+        return OutputTransform {
+            exposure: 0.0,
+            tone_map: ToneMap::Clamp,
+            encoding: Encoding::Srgb,
+        };
+    }
+}
+
+impl OutputTransform {
+    pub fn apply(&self, color: DVec3) -> DVec3 {
+        let scale = self.exposure.exp2();
+        let exposed = DVec3::new(
+            sanitize(color.x) * scale,
+            sanitize(color.y) * scale,
+            sanitize(color.z) * scale,
+        );
+        let mapped = match self.tone_map {
+            ToneMap::Clamp => exposed,
+            ToneMap::Reinhard => exposed / (exposed + 1.0),
+            ToneMap::Aces => {
+                (exposed * (2.51 * exposed + 0.03)) / (exposed * (2.43 * exposed + 0.59) + 0.14)
+            }
+        };
+        let clamped = mapped.clamp(DVec3::ZERO, DVec3::ONE);
+        return DVec3::new(
+            self.encode(clamped.x),
+            self.encode(clamped.y),
+            self.encode(clamped.z),
+        );
+    }
+
+    fn encode(&self, value: f64) -> f64 {
+        return match self.encoding {
+            Encoding::Linear => value,
+            Encoding::Gamma22 => value.powf(1.0 / 2.2),
+            Encoding::Srgb => {
+                if value <= 0.0031308 {
+                    12.92 * value
+                } else {
+                    1.055 * value.powf(1.0 / 2.4) - 0.055
+                }
+            }
+        };
+    }
+}
+
+/// NaNs and negative values come from numerical accidents in the integrator and must
+/// not poison the output.
+fn sanitize(value: f64) -> f64 {
+    if value.is_nan() || value < 0.0 {
+        return 0.0;
+    }
+    return value;
+}
+
+impl FromStr for ToneMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s.to_ascii_lowercase().as_str() {
+            "clamp" | "none" => Ok(ToneMap::Clamp),
+            "reinhard" => Ok(ToneMap::Reinhard),
+            "aces" => Ok(ToneMap::Aces),
+            _ => Err(format!(
+                "unknown tone map '{}' (expected clamp, reinhard or aces)",
+                s
+            )),
+        };
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s.to_ascii_lowercase().as_str() {
+            "linear" => Ok(Encoding::Linear),
+            "gamma2.2" | "gamma22" => Ok(Encoding::Gamma22),
+            "srgb" => Ok(Encoding::Srgb),
+            _ => Err(format!(
+                "unknown encoding '{}' (expected linear, gamma2.2 or srgb)",
+                s
+            )),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transform(exposure: f64, tone_map: ToneMap, encoding: Encoding) -> OutputTransform {
+        return OutputTransform {
+            exposure,
+            tone_map,
+            encoding,
+        };
+    }
+
+    /// Output of a gray input of the given value.
+    fn gray(transform: &OutputTransform, value: f64) -> f64 {
+        let color = transform.apply(DVec3::splat(value));
+        assert!(color.x == color.y && color.y == color.z);
+        return color.x;
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn encodings_follow_their_transfer_curves() {
+        let srgb = transform(0.0, ToneMap::Clamp, Encoding::Srgb);
+        assert_close(gray(&srgb, 0.0), 0.0);
+        // Linear segment and its end, where the power segment takes over.
+        assert_close(gray(&srgb, 0.001), 0.01292);
+        assert_close(gray(&srgb, 0.0031308), 0.04045);
+        assert_close(gray(&srgb, 0.5), 0.735357);
+        assert_close(gray(&srgb, 1.0), 1.0);
+
+        let gamma = transform(0.0, ToneMap::Clamp, Encoding::Gamma22);
+        assert_close(gray(&gamma, 0.5f64.powf(2.2)), 0.5);
+        assert_close(gray(&gamma, 1.0), 1.0);
+
+        let linear = transform(0.0, ToneMap::Clamp, Encoding::Linear);
+        assert_close(gray(&linear, 0.3), 0.3);
+        assert_close(gray(&linear, 7.0), 1.0);
+    }
+
+    #[test]
+    fn tone_maps_compress_highlights() {
+        let reinhard = transform(0.0, ToneMap::Reinhard, Encoding::Linear);
+        assert_close(gray(&reinhard, 1.0), 0.5);
+        assert_close(gray(&reinhard, 3.0), 0.75);
+        assert!(gray(&reinhard, 1e6) < 1.0);
+
+        let aces = transform(0.0, ToneMap::Aces, Encoding::Linear);
+        assert_close(gray(&aces, 0.0), 0.0);
+        assert_close(gray(&aces, 1.0), 2.54 / 3.16);
+        assert_close(gray(&aces, 1e6), 1.0);
+    }
+
+    #[test]
+    fn each_stop_of_exposure_doubles_the_radiance() {
+        for stops in [-2.0, -1.0, 0.0, 1.0, 3.0] {
+            let linear = transform(stops, ToneMap::Clamp, Encoding::Linear);
+            assert_close(gray(&linear, 0.1), 0.1 * 2f64.powf(stops));
+        }
+    }
+
+    #[test]
+    fn nan_and_negative_values_become_black() {
+        for tone_map in [ToneMap::Clamp, ToneMap::Reinhard, ToneMap::Aces] {
+            let transform = transform(1.0, tone_map, Encoding::Srgb);
+            let color = transform.apply(DVec3::new(f64::NAN, -4.0, -0.0));
+            assert_eq!(color, DVec3::ZERO, "{:?}", tone_map);
+        }
+        let color = DVec3::new(f64::NAN, -1.0, 0.5);
+        let transform = transform(0.0, ToneMap::Clamp, Encoding::Linear);
+        assert_eq!(crate::output::quantize(color, &transform), [0, 0, 127]);
+    }
+}
@@ -0,0 +1,157 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use glam::DVec3;
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::ray::Ray;
+
+/// Solid cone with a capped circular base. On the side, u is the angle around the
+/// axis and v the height; on the base v is the distance from the axis.
+pub struct Cone {
+    pub base: DVec3,
+    pub apex: DVec3,
+    /// Radius of the base.
+    pub radius: f64,
+    pub material: Arc<dyn Material>,
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        // Frame with the axis along +z, base at z = 0 and apex at z = height. The side
+        // satisfies x² + y² = k² (height - z)².
+        let height = (self.apex - self.base).length();
+        let k = self.radius / height;
+        let k2 = k * k;
+        let onb = Onb::from_w(self.apex - self.base);
+        let o = onb.to_local(ray.origin - self.base);
+        let d = onb.to_local(ray.dir);
+
+        let mut closest: Option<(f64, DVec3)> = None;
+        let mut consider = |t: f64, local_normal: DVec3| {
+            if t_min <= t && t <= t_max && closest.is_none_or(|(best, _)| t < best) {
+                closest = Some((t, local_normal));
+            }
+        };
+        let mut side_hit = |t: f64| {
+            let p = o + t * d;
+            if (0.0..=height).contains(&p.z) {
+                consider(t, DVec3::new(p.x, p.y, k2 * (height - p.z)).normalize());
+            }
+        };
+
+        let hz = height - o.z;
+        let a = d.x * d.x + d.y * d.y - k2 * d.z * d.z;
+        let half_b = o.x * d.x + o.y * d.y + k2 * hz * d.z;
+        let c = o.x * o.x + o.y * o.y - k2 * hz * hz;
+        if a.abs() < 1e-12 {
+            // Ray parallel to the side, at most one intersection.
+            if half_b != 0.0 {
+                side_hit(-c / (2.0 * half_b));
+            }
+        } else {
+            let discriminant = half_b * half_b - a * c;
+            if discriminant >= 0.0 {
+                let sqrt = discriminant.sqrt();
+                side_hit((-half_b - sqrt) / a);
+                side_hit((-half_b + sqrt) / a);
+            }
+        }
+
+        if d.z != 0.0 {
+            let t = -o.z / d.z;
+            let p = o + t * d;
+            if p.x * p.x + p.y * p.y <= self.radius * self.radius {
+                consider(t, DVec3::NEG_Z);
+            }
+        }
+
+        let Some((t, local_normal)) = closest else {
+            return false;
+        };
+        let local = o + t * d;
+        rec.t = t;
+        rec.p = ray.at(t);
+        rec.set_face_normal(ray, onb.to_world(local_normal));
+        rec.u = local.y.atan2(local.x) / (2.0 * PI) + 0.5;
+        rec.v = if local_normal == DVec3::NEG_Z {
+            (local.x * local.x + local.y * local.y).sqrt() / self.radius
+        } else {
+            local.z / height
+        };
+        rec.material = Some(self.material.clone());
+        return true;
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let extent = DVec3::splat(self.radius);
+        let base = Aabb::new(self.base - extent, self.base + extent);
+        return Aabb::surrounding(&base, &Aabb::new(self.apex, self.apex));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    fn record() -> HitRecord {
+        return HitRecord {
+            p: DVec3::ZERO,
+            normal: DVec3::ZERO,
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: false,
+            material: None,
+        };
+    }
+
+    fn cone() -> Cone {
+        return Cone {
+            base: DVec3::ZERO,
+            apex: DVec3::new(0.0, 1.0, 0.0),
+            radius: 1.0,
+            material: Arc::new(Lambertian::new(DVec3::splat(0.5))),
+        };
+    }
+
+    #[test]
+    fn hits_slanted_side() {
+        let ray = Ray {
+            origin: DVec3::new(0.0, 0.5, 5.0),
+            dir: DVec3::NEG_Z,
+            time: 0.0,
+        };
+        let mut rec = record();
+        assert!(cone().hit(&ray, 0.0, f64::INFINITY, &mut rec));
+        // Radius is 0.5 halfway up.
+        assert!((rec.t - 4.5).abs() < 1e-9);
+        let expected = DVec3::new(0.0, 1.0, 1.0).normalize();
+        assert!((rec.normal - expected).length() < 1e-9);
+        assert!((rec.v - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn hits_base_from_below_and_misses_above_apex() {
+        let mut rec = record();
+        let below = Ray {
+            origin: DVec3::new(0.2, -3.0, 0.0),
+            dir: DVec3::Y,
+            time: 0.0,
+        };
+        assert!(cone().hit(&below, 0.0, f64::INFINITY, &mut rec));
+        assert!((rec.t - 3.0).abs() < 1e-9);
+        assert!((rec.normal - DVec3::NEG_Y).length() < 1e-9);
+
+        let above = Ray {
+            origin: DVec3::new(0.0, 1.5, 5.0),
+            dir: DVec3::NEG_Z,
+            time: 0.0,
+        };
+        assert!(!cone().hit(&above, 0.0, f64::INFINITY, &mut rec));
+    }
+}
@@ -0,0 +1,175 @@
+use std::sync::Arc;
+
+use glam::DVec3;
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+
+/// Solid axis-aligned box. Each face is mapped to the full [0, 1]² UV range.
+pub struct Cuboid {
+    pub min: DVec3,
+    pub max: DVec3,
+    pub material: Arc<dyn Material>,
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        // Slab test that also remembers which face the ray enters and leaves through.
+        let mut t_enter = f64::NEG_INFINITY;
+        let mut t_exit = f64::INFINITY;
+        let mut enter_axis = 0;
+        let mut exit_axis = 0;
+        for axis in 0..3 {
+            let inv_d = 1.0 / ray.dir[axis];
+            let mut t0 = (self.min[axis] - ray.origin[axis]) * inv_d;
+            let mut t1 = (self.max[axis] - ray.origin[axis]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            if t0 > t_enter {
+                t_enter = t0;
+                enter_axis = axis;
+            }
+            if t1 < t_exit {
+                t_exit = t1;
+                exit_axis = axis;
+            }
+        }
+        if t_exit < t_enter {
+            return false;
+        }
+
+        let (t, axis) = if t_min <= t_enter && t_enter <= t_max {
+            (t_enter, enter_axis)
+        } else if t_min <= t_exit && t_exit <= t_max {
+            (t_exit, exit_axis)
+        } else {
+            return false;
+        };
+
+        rec.t = t;
+        rec.p = ray.at(t);
+        let center = 0.5 * (self.min + self.max);
+        let mut outward_normal = DVec3::ZERO;
+        outward_normal[axis] = if rec.p[axis] > center[axis] {
+            1.0
+        } else {
+            -1.0
+        };
+        rec.set_face_normal(ray, outward_normal);
+
+        // A box that is flat along an axis has no extent to map there.
+        let size = self.max - self.min;
+        let relative = DVec3::select(
+            size.cmpgt(DVec3::ZERO),
+            (rec.p - self.min) / size,
+            DVec3::splat(0.5),
+        );
+        let (u_axis, v_axis) = match axis {
+            0 => (2, 1),
+            1 => (0, 2),
+            _ => (0, 1),
+        };
+        rec.u = relative[u_axis];
+        rec.v = relative[v_axis];
+        rec.material = Some(self.material.clone());
+        return true;
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        return Aabb::new(self.min, self.max);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    fn record() -> HitRecord {
+        return HitRecord {
+            p: DVec3::ZERO,
+            normal: DVec3::ZERO,
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: false,
+            material: None,
+        };
+    }
+
+    fn cube() -> Cuboid {
+        return Cuboid {
+            min: DVec3::splat(-1.0),
+            max: DVec3::splat(1.0),
+            material: Arc::new(Lambertian::new(DVec3::splat(0.5))),
+        };
+    }
+
+    #[test]
+    fn hits_entry_face_from_outside() {
+        let ray = Ray {
+            origin: DVec3::new(0.5, 0.0, 5.0),
+            dir: DVec3::NEG_Z,
+            time: 0.0,
+        };
+        let mut rec = record();
+        assert!(cube().hit(&ray, 0.0, f64::INFINITY, &mut rec));
+        assert!((rec.t - 4.0).abs() < 1e-9);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, DVec3::Z);
+        assert!((rec.u - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn flat_boxes_have_finite_uvs() {
+        let floor = Cuboid {
+            min: DVec3::new(-1.0, 0.0, -1.0),
+            max: DVec3::new(1.0, 0.0, 1.0),
+            ..cube()
+        };
+        let ray = Ray {
+            origin: DVec3::new(0.5, 2.0, 0.0),
+            dir: DVec3::NEG_Y,
+            time: 0.0,
+        };
+        let mut rec = record();
+        assert!(floor.hit(&ray, 0.0, f64::INFINITY, &mut rec));
+        assert!((rec.t - 2.0).abs() < 1e-9);
+        assert!((rec.u - 0.75).abs() < 1e-9);
+        assert!((rec.v - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn hits_exit_face_from_inside() {
+        let ray = Ray {
+            origin: DVec3::ZERO,
+            dir: DVec3::new(0.0, -2.0, 0.0),
+            time: 0.0,
+        };
+        let mut rec = record();
+        assert!(cube().hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert!((rec.t - 0.5).abs() < 1e-9);
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, DVec3::Y);
+    }
+
+    #[test]
+    fn misses_beside_and_behind() {
+        let mut rec = record();
+        let beside = Ray {
+            origin: DVec3::new(1.5, 0.0, 5.0),
+            dir: DVec3::NEG_Z,
+            time: 0.0,
+        };
+        assert!(!cube().hit(&beside, 0.0, f64::INFINITY, &mut rec));
+        let behind = Ray {
+            origin: DVec3::new(0.0, 0.0, 5.0),
+            dir: DVec3::Z,
+            time: 0.0,
+        };
+        assert!(!cube().hit(&behind, 0.0, f64::INFINITY, &mut rec));
+    }
+}
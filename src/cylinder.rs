@@ -0,0 +1,156 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use glam::DVec3;
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::ray::Ray;
+
+/// Solid cylinder between the centers of its two caps. On the side, u is the angle
+/// around the axis and v the height; on the caps v is the distance from the axis.
+pub struct Cylinder {
+    pub base: DVec3,
+    pub top: DVec3,
+    pub radius: f64,
+    pub material: Arc<dyn Material>,
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        // Work in a frame where the axis is +z and the base sits at the origin.
+        let height = (self.top - self.base).length();
+        let onb = Onb::from_w(self.top - self.base);
+        let o = onb.to_local(ray.origin - self.base);
+        let d = onb.to_local(ray.dir);
+
+        let mut closest: Option<(f64, DVec3)> = None;
+        let mut consider = |t: f64, local_normal: DVec3| {
+            if t_min <= t && t <= t_max && closest.is_none_or(|(best, _)| t < best) {
+                closest = Some((t, local_normal));
+            }
+        };
+
+        let a = d.x * d.x + d.y * d.y;
+        if a > 0.0 {
+            let half_b = o.x * d.x + o.y * d.y;
+            let c = o.x * o.x + o.y * o.y - self.radius * self.radius;
+            let discriminant = half_b * half_b - a * c;
+            if discriminant >= 0.0 {
+                let sqrt = discriminant.sqrt();
+                for t in [(-half_b - sqrt) / a, (-half_b + sqrt) / a] {
+                    let p = o + t * d;
+                    if (0.0..=height).contains(&p.z) {
+                        consider(t, DVec3::new(p.x, p.y, 0.0) / self.radius);
+                    }
+                }
+            }
+        }
+
+        if d.z != 0.0 {
+            for (z, normal) in [(0.0, DVec3::NEG_Z), (height, DVec3::Z)] {
+                let t = (z - o.z) / d.z;
+                let p = o + t * d;
+                if p.x * p.x + p.y * p.y <= self.radius * self.radius {
+                    consider(t, normal);
+                }
+            }
+        }
+
+        let Some((t, local_normal)) = closest else {
+            return false;
+        };
+        let local = o + t * d;
+        rec.t = t;
+        rec.p = ray.at(t);
+        rec.set_face_normal(ray, onb.to_world(local_normal));
+        rec.u = local.y.atan2(local.x) / (2.0 * PI) + 0.5;
+        rec.v = if local_normal.z == 0.0 {
+            local.z / height
+        } else {
+            (local.x * local.x + local.y * local.y).sqrt() / self.radius
+        };
+        rec.material = Some(self.material.clone());
+        return true;
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let extent = DVec3::splat(self.radius);
+        let a = Aabb::new(self.base - extent, self.base + extent);
+        let b = Aabb::new(self.top - extent, self.top + extent);
+        return Aabb::surrounding(&a, &b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    fn record() -> HitRecord {
+        return HitRecord {
+            p: DVec3::ZERO,
+            normal: DVec3::ZERO,
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: false,
+            material: None,
+        };
+    }
+
+    fn cylinder() -> Cylinder {
+        return Cylinder {
+            base: DVec3::ZERO,
+            top: DVec3::new(0.0, 2.0, 0.0),
+            radius: 1.0,
+            material: Arc::new(Lambertian::new(DVec3::splat(0.5))),
+        };
+    }
+
+    #[test]
+    fn hits_side_and_caps() {
+        let mut rec = record();
+        let side = Ray {
+            origin: DVec3::new(0.0, 1.5, 5.0),
+            dir: DVec3::NEG_Z,
+            time: 0.0,
+        };
+        assert!(cylinder().hit(&side, 0.0, f64::INFINITY, &mut rec));
+        assert!((rec.t - 4.0).abs() < 1e-9);
+        assert!((rec.normal - DVec3::Z).length() < 1e-9);
+        assert!((rec.v - 0.75).abs() < 1e-9);
+
+        let top = Ray {
+            origin: DVec3::new(0.5, 5.0, 0.0),
+            dir: DVec3::NEG_Y,
+            time: 0.0,
+        };
+        assert!(cylinder().hit(&top, 0.0, f64::INFINITY, &mut rec));
+        assert!((rec.t - 3.0).abs() < 1e-9);
+        assert!((rec.normal - DVec3::Y).length() < 1e-9);
+        assert!(rec.front_face);
+    }
+
+    #[test]
+    fn misses_above_and_reports_exit_from_inside() {
+        let mut rec = record();
+        let above = Ray {
+            origin: DVec3::new(0.0, 2.5, 5.0),
+            dir: DVec3::NEG_Z,
+            time: 0.0,
+        };
+        assert!(!cylinder().hit(&above, 0.0, f64::INFINITY, &mut rec));
+
+        let inside = Ray {
+            origin: DVec3::new(0.0, 1.0, 0.0),
+            dir: DVec3::X,
+            time: 0.0,
+        };
+        assert!(cylinder().hit(&inside, 0.001, f64::INFINITY, &mut rec));
+        assert!((rec.t - 1.0).abs() < 1e-9);
+        assert!(!rec.front_face);
+    }
+}
@@ -0,0 +1,140 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use glam::DVec3;
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::ray::Ray;
+
+/// Flat disk. u is the angle around the center and v the distance from it, both
+/// mapped to [0, 1].
+pub struct Disk {
+    pub center: DVec3,
+    /// Front side of the disk, does not need to be normalized.
+    pub normal: DVec3,
+    pub radius: f64,
+    pub material: Arc<dyn Material>,
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let onb = Onb::from_w(self.normal);
+        let denom = onb.w.dot(ray.dir);
+        if denom.abs() < 1e-12 {
+            return false;
+        }
+        let t = onb.w.dot(self.center - ray.origin) / denom;
+        if t < t_min || t_max < t {
+            return false;
+        }
+        let p = ray.at(t);
+        let local = onb.to_local(p - self.center);
+        let distance = local.truncate().length();
+        if distance > self.radius {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = local.y.atan2(local.x) / (2.0 * PI) + 0.5;
+        rec.v = distance / self.radius;
+        rec.set_face_normal(ray, onb.w);
+        rec.material = Some(self.material.clone());
+        return true;
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        // Per axis, the disk extends radius * sin(angle between axis and normal).
+        let n = self.normal.normalize();
+        let extent = self.radius * (DVec3::ONE - n * n).max(DVec3::ZERO).powf(0.5);
+        return Aabb::new(self.center - extent, self.center + extent);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    fn record() -> HitRecord {
+        return HitRecord {
+            p: DVec3::ZERO,
+            normal: DVec3::ZERO,
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: false,
+            material: None,
+        };
+    }
+
+    fn disk(normal: DVec3) -> Disk {
+        return Disk {
+            center: DVec3::new(1.0, 2.0, 3.0),
+            normal,
+            radius: 2.0,
+            material: Arc::new(Lambertian::new(DVec3::splat(0.5))),
+        };
+    }
+
+    fn hit(origin: DVec3, dir: DVec3) -> Option<HitRecord> {
+        let ray = Ray {
+            origin,
+            dir,
+            time: 0.0,
+        };
+        let mut rec = record();
+        return disk(DVec3::new(0.0, 0.0, 3.0))
+            .hit(&ray, 0.001, f64::INFINITY, &mut rec)
+            .then_some(rec);
+    }
+
+    #[test]
+    fn hits_face_the_incoming_ray() {
+        let rec = hit(DVec3::new(1.0, 2.0, 8.0), DVec3::NEG_Z).unwrap();
+        assert!((rec.t - 5.0).abs() < 1e-9);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, DVec3::Z);
+        assert_eq!(rec.v, 0.0);
+
+        let rec = hit(DVec3::new(1.5, 2.0, 1.0), DVec3::Z).unwrap();
+        assert!((rec.t - 2.0).abs() < 1e-9);
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, DVec3::NEG_Z);
+    }
+
+    #[test]
+    fn uvs_are_angle_and_distance() {
+        let a = hit(DVec3::new(2.0, 2.0, 8.0), DVec3::NEG_Z).unwrap();
+        let b = hit(DVec3::new(0.0, 2.0, 8.0), DVec3::NEG_Z).unwrap();
+        assert!((a.v - 0.5).abs() < 1e-9 && (b.v - 0.5).abs() < 1e-9);
+        // Opposite points are half a turn apart.
+        assert!(((a.u - b.u).abs() - 0.5).abs() < 1e-9);
+        assert!((0.0..=1.0).contains(&a.u) && (0.0..=1.0).contains(&b.u));
+
+        let rim = hit(DVec3::new(2.999, 2.0, 8.0), DVec3::NEG_Z).unwrap();
+        assert!((rim.v - 0.9995).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rays_outside_the_radius_or_parallel_miss() {
+        assert!(hit(DVec3::new(3.001, 2.0, 8.0), DVec3::NEG_Z).is_none());
+        assert!(hit(DVec3::new(1.0, 3.0, 8.0), DVec3::new(0.0, 1.5, -1.0)).is_none());
+        assert!(hit(DVec3::new(1.0, 2.0, 4.0), DVec3::X).is_none());
+    }
+
+    #[test]
+    fn bounding_box_follows_the_tilt() {
+        let bbox = disk(DVec3::Z).bounding_box();
+        assert_eq!(bbox.min, DVec3::new(-1.0, 0.0, 3.0));
+        assert_eq!(bbox.max, DVec3::new(3.0, 4.0, 3.0));
+
+        let bbox = disk(DVec3::new(1.0, 0.0, 1.0)).bounding_box();
+        let extent = DVec3::new(2f64.sqrt(), 2.0, 2f64.sqrt());
+        assert!((bbox.min - (DVec3::new(1.0, 2.0, 3.0) - extent)).length() < 1e-9);
+        assert!((bbox.max - (DVec3::new(1.0, 2.0, 3.0) + extent)).length() < 1e-9);
+    }
+}
@@ -0,0 +1,193 @@
+use std::sync::Arc;
+
+use glam::DVec3;
+
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::random::Rng;
+use crate::ray::Ray;
+
+/// Details of a ray-surface intersection.
+pub struct HitRecord {
+    pub p: DVec3,
+    /// Unit normal, always facing against the incoming ray.
+    pub normal: DVec3,
+    pub t: f64,
+    /// Surface coordinates of the hit point, used for texturing.
+    pub u: f64,
+    pub v: f64,
+    /// Whether the ray hit the outside of the surface.
+    pub front_face: bool,
+    pub material: Option<Arc<dyn Material>>,
+}
+
+impl HitRecord {
+    /// Stores `outward_normal` (assumed unit length) flipped to face the ray, and
+    /// records which side of the surface was hit.
+    pub fn set_face_normal(&mut self, ray: &Ray, outward_normal: DVec3) {
+        self.front_face = ray.dir.dot(outward_normal) < 0.0;
+        self.normal = if self.front_face {
+            outward_normal
+        } else {
+            -outward_normal
+        };
+    }
+}
+
+/// Anything a ray can intersect.
+pub trait Hittable: Send + Sync {
+    /// Finds the closest intersection with parameter in [t_min, t_max]. On a hit, fills
+    /// in `rec` and returns true; otherwise `rec` is left untouched.
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool;
+    /// Box enclosing the whole object, used to build acceleration structures.
+    fn bounding_box(&self) -> Aabb;
+
+    /// Density, per unit solid angle, with which [`Hittable::random`] picks
+    /// `direction` from `origin`. Objects that cannot be sampled as lights keep the
+    /// default of zero.
+    fn pdf_value(&self, _origin: DVec3, _direction: DVec3) -> f64 {
+        return 0.0;
+    }
+
+    /// Random direction from `origin` towards the object, used to aim shadow rays
+    /// at lights.
+    fn random(&self, _origin: DVec3, _rng: &mut Rng) -> DVec3 {
+        return DVec3::X;
+    }
+}
+
+/// Unordered collection of objects tested one after the other.
+pub struct HittableList {
+    pub objects: Vec<Box<dyn Hittable>>,
+}
+
+impl Hittable for HittableList {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let mut temp_rec = HitRecord {
+            p: DVec3::new(0.0, 0.0, 0.0),
+            normal: DVec3::new(0.0, 0.0, 0.0),
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: false,
+            material: None,
+        };
+        let mut hit_anything = false;
+        let mut closest_so_far = t_max;
+
+        for object in self.objects.iter() {
+            if object.hit(ray, t_min, closest_so_far, &mut temp_rec) {
+                hit_anything = true;
+                closest_so_far = temp_rec.t;
+                rec.p = temp_rec.p;
+                rec.normal = temp_rec.normal;
+                rec.t = temp_rec.t;
+                rec.u = temp_rec.u;
+                rec.v = temp_rec.v;
+                rec.front_face = temp_rec.front_face;
+                rec.material = temp_rec.material.clone();
+            }
+        }
+
+        return hit_anything;
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        return self.objects.iter().fold(Aabb::EMPTY, |acc, object| {
+            Aabb::surrounding(&acc, &object.bounding_box())
+        });
+    }
+
+    /// Mixture of the objects' densities, matching [`HittableList::random`]'s choice
+    /// of one object with equal probability.
+    fn pdf_value(&self, origin: DVec3, direction: DVec3) -> f64 {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+        let sum: f64 = self
+            .objects
+            .iter()
+            .map(|object| object.pdf_value(origin, direction))
+            .sum();
+        return sum / self.objects.len() as f64;
+    }
+
+    fn random(&self, origin: DVec3, rng: &mut Rng) -> DVec3 {
+        if self.objects.is_empty() {
+            return DVec3::X;
+        }
+        let index =
+            ((rng.next_f64() * self.objects.len() as f64) as usize).min(self.objects.len() - 1);
+        return self.objects[index].random(origin, rng);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> HitRecord {
+        return HitRecord {
+            p: DVec3::ZERO,
+            normal: DVec3::ZERO,
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: false,
+            material: None,
+        };
+    }
+
+    #[test]
+    fn set_face_normal_keeps_normal_against_ray() {
+        let mut rec = record();
+        let ray = Ray {
+            origin: DVec3::new(0.0, 0.0, 5.0),
+            dir: DVec3::NEG_Z,
+            time: 0.0,
+        };
+        rec.set_face_normal(&ray, DVec3::Z);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, DVec3::Z);
+    }
+
+    #[test]
+    fn set_face_normal_flips_normal_along_ray() {
+        let mut rec = record();
+        let ray = Ray {
+            origin: DVec3::ZERO,
+            dir: DVec3::new(0.3, 0.0, -1.0),
+            time: 0.0,
+        };
+        rec.set_face_normal(&ray, DVec3::NEG_Z);
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, DVec3::Z);
+    }
+
+    #[test]
+    fn grazing_ray_counts_as_back_face() {
+        let mut rec = record();
+        let ray = Ray {
+            origin: DVec3::ZERO,
+            dir: DVec3::X,
+            time: 0.0,
+        };
+        rec.set_face_normal(&ray, DVec3::Y);
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, DVec3::NEG_Y);
+    }
+
+    #[test]
+    fn empty_list_never_hits() {
+        let list = HittableList {
+            objects: Vec::new(),
+        };
+        let ray = Ray {
+            origin: DVec3::ZERO,
+            dir: DVec3::X,
+            time: 0.0,
+        };
+        assert!(!list.hit(&ray, 0.0, f64::INFINITY, &mut record()));
+        assert_eq!(list.bounding_box(), Aabb::EMPTY);
+    }
+}
@@ -0,0 +1,30 @@
+use glam::DVec3;
+
+/// In-memory framebuffer of linear HDR colors, stored row by row from the top-left.
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<DVec3>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Image {
+        return Image {
+            width,
+            height,
+            pixels: vec![DVec3::ZERO; width * height],
+        };
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> DVec3 {
+        return self.pixels[y * self.width + x];
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: DVec3) {
+        self.pixels[y * self.width + x] = color;
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[DVec3]> {
+        return self.pixels.chunks(self.width);
+    }
+}
@@ -0,0 +1,209 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::Path;
+
+use glam::DVec3;
+
+use crate::image::Image;
+
+/// Decodes the PNG or PPM (P3/P6) file at `path`. The stored values are
+/// treated as sRGB and converted to linear colors.
+pub fn load(path: &Path) -> io::Result<Image> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    return match extension.as_deref() {
+        Some("png") => read_png(BufReader::new(File::open(path)?)),
+        Some("ppm") => read_ppm(&fs::read(path)?),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unsupported image format '{}'", path.display()),
+        )),
+    };
+}
+
+/// Reads a PNG of any color type; alpha is ignored.
+pub fn read_png(input: impl io::BufRead + io::Seek) -> io::Result<Image> {
+    let mut decoder = png::Decoder::new(input);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    let channels = info.color_type.samples();
+    let mut image = Image::new(info.width as usize, info.height as usize);
+    for (index, pixel) in buffer[..info.buffer_size()]
+        .chunks_exact(channels)
+        .enumerate()
+    {
+        let color = match channels {
+            1 | 2 => DVec3::splat(pixel[0] as f64),
+            _ => DVec3::new(pixel[0] as f64, pixel[1] as f64, pixel[2] as f64),
+        };
+        image.set(
+            index % image.width,
+            index / image.width,
+            srgb_to_linear(color / 255.0),
+        );
+    }
+    return Ok(image);
+}
+
+/// Reads an ASCII (P3) or binary (P6) PPM with any maximum value.
+pub fn read_ppm(bytes: &[u8]) -> io::Result<Image> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut pos = 0;
+    let magic = next_token(bytes, &mut pos).ok_or_else(|| invalid("empty PPM file"))?;
+    let binary = match magic {
+        b"P3" => false,
+        b"P6" => true,
+        _ => return Err(invalid("expected a P3 or P6 PPM file")),
+    };
+    let mut header = [0usize; 3];
+    for value in header.iter_mut() {
+        *value = next_token(bytes, &mut pos)
+            .and_then(|token| std::str::from_utf8(token).ok())
+            .and_then(|token| token.parse().ok())
+            .filter(|&parsed| parsed > 0)
+            .ok_or_else(|| invalid("invalid PPM header"))?;
+    }
+    let [width, height, max_value] = header;
+    if max_value > 65535 {
+        return Err(invalid("PPM maximum value must be at most 65535"));
+    }
+
+    // The header is untrusted, so its size is checked against the data before any
+    // memory is reserved for it.
+    let count = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(3))
+        .ok_or_else(|| invalid("PPM dimensions are too large"))?;
+    let mut samples;
+    if binary {
+        // Exactly one whitespace character separates the header from the data.
+        let data = bytes.get(pos + 1..).unwrap_or(&[]);
+        let size = if max_value < 256 { 1 } else { 2 };
+        if data.len() / size < count {
+            return Err(invalid("PPM pixel data is truncated"));
+        }
+        samples = Vec::with_capacity(count);
+        for chunk in data.chunks_exact(size).take(count) {
+            samples.push(chunk.iter().fold(0usize, |acc, &b| acc << 8 | b as usize));
+        }
+    } else {
+        // Every ASCII sample takes at least two bytes including its separator.
+        samples = Vec::with_capacity(count.min(bytes.len() / 2));
+        for _ in 0..count {
+            let sample = next_token(bytes, &mut pos)
+                .and_then(|token| std::str::from_utf8(token).ok())
+                .and_then(|token| token.parse().ok())
+                .ok_or_else(|| invalid("invalid or missing PPM pixel value"))?;
+            samples.push(sample);
+        }
+    }
+
+    let mut image = Image::new(width, height);
+    for (index, rgb) in samples.chunks_exact(3).enumerate() {
+        let color = DVec3::new(rgb[0] as f64, rgb[1] as f64, rgb[2] as f64) / max_value as f64;
+        image.set(
+            index % width,
+            index / width,
+            srgb_to_linear(color.min(DVec3::ONE)),
+        );
+    }
+    return Ok(image);
+}
+
+/// Next whitespace separated token of a PPM header or ASCII body, skipping comments.
+/// Leaves `pos` right after the token.
+fn next_token<'a>(bytes: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+    loop {
+        while *pos < bytes.len() && bytes[*pos].is_ascii_whitespace() {
+            *pos += 1;
+        }
+        if bytes.get(*pos) != Some(&b'#') {
+            break;
+        }
+        while *pos < bytes.len() && bytes[*pos] != b'\n' {
+            *pos += 1;
+        }
+    }
+    let start = *pos;
+    while *pos < bytes.len() && !bytes[*pos].is_ascii_whitespace() {
+        *pos += 1;
+    }
+    return if start == *pos {
+        None
+    } else {
+        Some(&bytes[start..*pos])
+    };
+}
+
+/// Inverse of the sRGB transfer curve.
+fn srgb_to_linear(color: DVec3) -> DVec3 {
+    let decode = |value: f64| {
+        if value <= 0.04045 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    return DVec3::new(decode(color.x), decode(color.y), decode(color.z));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::OutputTransform;
+    use crate::output;
+
+    #[test]
+    fn ppm_round_trips_through_output() {
+        let mut image = Image::new(2, 1);
+        image.set(0, 0, DVec3::new(1.0, 0.0, 0.0));
+        image.set(1, 0, DVec3::new(0.0, 0.5, 1.0));
+        let transform = OutputTransform::default();
+        for write in [output::write_ppm_ascii, output::write_ppm_binary] {
+            let mut bytes = Vec::new();
+            write(&mut bytes, &image, &transform).unwrap();
+            let decoded = read_ppm(&bytes).unwrap();
+            assert_eq!((decoded.width, decoded.height), (2, 1));
+            assert!((decoded.get(0, 0) - DVec3::new(1.0, 0.0, 0.0)).length() < 1e-3);
+            assert!((decoded.get(1, 0) - DVec3::new(0.0, 0.5, 1.0)).length() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn png_round_trips_through_output() {
+        let mut image = Image::new(1, 2);
+        image.set(0, 1, DVec3::new(0.25, 0.5, 0.75));
+        let mut bytes = Vec::new();
+        output::write_png(&mut bytes, &image, &OutputTransform::default()).unwrap();
+        let decoded = read_png(io::Cursor::new(bytes)).unwrap();
+        assert_eq!((decoded.width, decoded.height), (1, 2));
+        assert_eq!(decoded.get(0, 0), DVec3::ZERO);
+        assert!((decoded.get(0, 1) - DVec3::new(0.25, 0.5, 0.75)).length() < 1e-2);
+    }
+
+    #[test]
+    fn ppm_header_comments_and_errors() {
+        let decoded = read_ppm(b"P3 # comment\n1 1\n# another\n15\n15 0 0\n").unwrap();
+        assert_eq!(decoded.get(0, 0), DVec3::new(1.0, 0.0, 0.0));
+        assert!(read_ppm(b"P5 1 1 255 0").is_err());
+        assert!(read_ppm(b"P6 2 2 255 \x00\x00\x00").is_err());
+    }
+
+    #[test]
+    fn huge_ppm_headers_are_rejected_without_allocating() {
+        let err = read_ppm(b"P6 4000000000 4000000000 255\n\x00")
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "PPM dimensions are too large");
+        let err = read_ppm(b"P6 200000 200000 255\n\x00\x00\x00")
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "PPM pixel data is truncated");
+        let err = read_ppm(b"P3 200000 200000 255\n1 2 3\n").err().unwrap();
+        assert_eq!(err.to_string(), "invalid or missing PPM pixel value");
+    }
+}
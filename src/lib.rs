@@ -0,0 +1,77 @@
+//! A small path tracer.
+//!
+//! Scenes are built from [`Hittable`] objects (for example [`Sphere`]s) carrying a
+//! [`material::Material`], collected in a [`HittableList`] or a [`bvh::BvhNode`].
+//! A [`Camera`] turns pixels into rays, [`render::render`] fills an [`Image`] with
+//! linear radiance, and the [`output`] module encodes it to PPM, PNG or HDR.
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use glam::DVec3;
+//! use raytracer_rs::material::Lambertian;
+//! use raytracer_rs::render::{render, Background, MisHeuristic, RenderSettings};
+//! use raytracer_rs::sampler::Stratified;
+//! use raytracer_rs::{Camera, CameraSettings, HittableList, Sphere};
+//!
+//! let world = HittableList {
+//!     objects: vec![Box::new(Sphere {
+//!         center: DVec3::new(0.0, 0.0, -1.0),
+//!         radius: 0.5,
+//!         material: Arc::new(Lambertian::new(DVec3::new(0.5, 0.5, 0.5))),
+//!     })],
+//! };
+//! let camera = Camera::new(CameraSettings {
+//!     image_width: 32,
+//!     ..CameraSettings::default()
+//! });
+//! let settings = RenderSettings {
+//!     samples_per_pixel: 4,
+//!     max_depth: 8,
+//!     seed: 0,
+//!     threads: 1,
+//!     background: Background::default(),
+//!     mis: MisHeuristic::Power,
+//! };
+//! let lights = HittableList { objects: vec![] };
+//! let image = render(&camera, &world, &lights, &Stratified, &settings);
+//! assert_eq!(image.width, 32);
+//! ```
+
+#![allow(clippy::needless_return)]
+
+pub mod aabb;
+pub mod bvh;
+pub mod camera;
+pub mod color;
+pub mod cone;
+pub mod cuboid;
+pub mod cylinder;
+pub mod disk;
+pub mod hittable;
+pub mod image;
+pub mod input;
+pub mod material;
+pub mod medium;
+pub mod obj;
+pub mod onb;
+pub mod output;
+pub mod perlin;
+pub mod plane;
+pub mod quad;
+pub mod random;
+pub mod ray;
+pub mod render;
+pub mod sampler;
+pub mod scene;
+pub mod sphere;
+pub mod texture;
+pub mod torus;
+pub mod transform;
+pub mod triangle;
+
+pub use camera::{Camera, CameraSettings};
+pub use hittable::{HitRecord, Hittable, HittableList};
+pub use image::Image;
+pub use ray::Ray;
+pub use sphere::Sphere;
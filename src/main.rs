@@ -1,189 +1,136 @@
-use std::cmp::max;
+#![allow(clippy::needless_return)]
 
-use glam::DVec3;
+mod cli;
 
-fn main() {
-    let aspect_ratio = 16.0 / 9.0;
-    let image_width = 400;
-    let image_height = max((image_width as f64 / aspect_ratio) as i32, 1);
-
-    let world = HittableList {
-        objects: vec![
-            Box::new(Sphere {
-                center: DVec3::new(0.0, 0.0, -1.0),
-                radius: 0.5,
-            }),
-            Box::new(Sphere {
-                center: DVec3::new(0.0, -100.5, -1.0),
-                radius: 100.0,
-            }),
-        ],
-    };
-
-    let focal_length = 1.0;
-    let viewport_height = 2.0;
-    let viewport_width = viewport_height * (image_width as f64 / image_height as f64);
-    let camera_center = DVec3::new(0.0, 0.0, 0.0);
-
-    let viewport_u = DVec3::new(viewport_width, 0.0, 0.0);
-    let viewport_v = DVec3::new(0.0, -viewport_height, 0.0);
-    eprintln!("{} {} {}", viewport_u.x, viewport_u.y, viewport_u.z);
-    eprintln!("{} {} {}", viewport_v.x, viewport_v.y, viewport_v.z);
-
-    let pixel_delta_u = viewport_u / image_width as f64;
-    let pixel_delta_v = viewport_v / image_height as f64;
-    eprintln!(
-        "{} {} {}",
-        pixel_delta_u.x, pixel_delta_u.y, pixel_delta_u.z
-    );
-    eprintln!(
-        "{} {} {}",
-        pixel_delta_v.x, pixel_delta_v.y, pixel_delta_v.z
-    );
+use std::fs;
+use std::io::{self, BufWriter};
+use std::path::Path;
+use std::process;
 
-    let viewport_upper_left =
-        camera_center - DVec3::new(0.0, 0.0, focal_length) - viewport_u / 2.0 - viewport_v / 2.0;
-    let pixel00_loc = viewport_upper_left + 0.5 * (pixel_delta_u + pixel_delta_v);
+use raytracer_rs::bvh;
+use raytracer_rs::color::OutputTransform;
+use raytracer_rs::output::{self, Format};
+use raytracer_rs::render::{render, render_progressive, RenderSettings};
+use raytracer_rs::{scene, Camera, Image};
 
-    println!("P3\n{} {}\n255", image_width, image_height);
+use cli::Command;
 
-    for (j, y) in (0..image_height).enumerate() {
-        eprintln!("\rScanlines remaining: {}", y);
-        for (i, _x) in (0..image_width).enumerate() {
-            let pixel_center =
-                pixel00_loc + ((i as f64) * pixel_delta_u) + ((j as f64) * pixel_delta_v);
-            let ray_direction = pixel_center - camera_center;
-            let ray = Ray {
-                origin: camera_center,
-                dir: ray_direction,
-            };
+const DEFAULT_SCENE: &str = include_str!("../scenes/default.scene");
 
-            let pixel_color = ray_color(ray, &world);
-            write_color(pixel_color);
+fn main() {
+    let options = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(Command::Render(options)) => options,
+        Ok(Command::Help) => {
+            print!("{}", cli::USAGE);
+            return;
+        }
+        Err(message) => {
+            eprintln!("error: {}\n\nRun with --help for usage.", message);
+            process::exit(2);
         }
-    }
-
-    eprintln!("\nDone.");
-}
-
-fn write_color(pixel_color: DVec3) {
-    println!(
-        "{} {} {}",
-        (255.999 * pixel_color.x).floor(),
-        (255.999 * pixel_color.y).floor(),
-        (255.999 * pixel_color.z).floor()
-    )
-}
-
-fn ray_color(ray: Ray, world: &HittableList) -> DVec3 {
-    let mut rec = HitRecord {
-        p: DVec3::new(0.0, 0.0, 0.0),
-        normal: DVec3::new(0.0, 0.0, 0.0),
-        t: 0.0,
-        front_face: false,
     };
-    if world.hit(&ray, 0.0, f64::INFINITY, &mut rec) {
-        return 0.5 * DVec3::new(rec.normal.x + 1.0, rec.normal.y + 1.0, rec.normal.z + 1.0);
-    }
-
-    let unit_direction = ray.dir.normalize();
-    let a = 0.5 * (unit_direction.y + 1.0);
-    return (1.0 - a) * DVec3::new(1.0, 1.0, 1.0) + a * DVec3::new(0.5, 0.7, 1.0);
-}
-
-struct Ray {
-    origin: DVec3,
-    dir: DVec3,
-}
 
-impl Ray {
-    fn at(&self, t: f64) -> DVec3 {
-        return self.origin + t * self.dir;
+    let scene = match &options.scene {
+        Some(path) => match fs::read_to_string(path) {
+            Ok(source) => scene::parse(&source, path.parent().unwrap_or(Path::new("")))
+                .map_err(|err| format!("{}:{}: {}", path.display(), err.line, err.message)),
+            Err(err) => Err(format!("failed to read '{}': {}", path.display(), err)),
+        },
+        None => scene::parse(DEFAULT_SCENE, Path::new(""))
+            .map_err(|err| format!("default scene: {}", err)),
+    };
+    let scene = match scene {
+        Ok(scene) => scene,
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(1);
+        }
+    };
+    let mut camera_settings = scene.camera;
+    match (options.width, options.height) {
+        (Some(width), Some(height)) => {
+            camera_settings.image_width = width;
+            camera_settings.image_height = Some(height);
+            camera_settings.aspect_ratio = width as f64 / height as f64;
+        }
+        (Some(width), None) => camera_settings.image_width = width,
+        (None, Some(height)) => {
+            camera_settings.image_width =
+                ((height as f64 * camera_settings.aspect_ratio) as i32).max(1);
+            camera_settings.image_height = Some(height);
+        }
+        (None, None) => {}
     }
-}
-
-struct HitRecord {
-    p: DVec3,
-    normal: DVec3,
-    t: f64,
-    front_face: bool,
-}
-
-impl HitRecord {
-    fn set_face_normal(&mut self, ray: &Ray, outward_normal: DVec3) {
-        self.front_face = ray.dir.dot(outward_normal) < 0.0;
-        self.normal = if self.front_face {
-            outward_normal
-        } else {
-            -outward_normal
-        };
+    let camera = Camera::new(camera_settings);
+    if let Err(message) = cli::check_image_size(camera.image_width, camera.image_height) {
+        eprintln!("error: {}", message);
+        process::exit(1);
     }
-}
-
-trait Hittable {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool;
-}
-
-struct Sphere {
-    center: DVec3,
-    radius: f64,
-}
-
-impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
-        let oc = ray.origin - self.center;
-        let a = ray.dir.length_squared();
-        let half_b = oc.dot(ray.dir);
-        let c = oc.length_squared() - self.radius * self.radius;
-        let discriminant = half_b * half_b - a * c;
 
-        if discriminant < 0.0 {
-            return false;
+    let settings = RenderSettings {
+        samples_per_pixel: options.samples_per_pixel,
+        max_depth: options.max_depth,
+        seed: options.seed,
+        threads: options.threads,
+        background: scene.background,
+        mis: options.mis,
+    };
+    let pattern = options.sampler.pattern();
+    let world = bvh::accelerate(scene.world.objects);
+    let transform = options.transform;
+    let image = match &options.progressive {
+        Some(progressive) => {
+            let mut checkpoint = |image: &Image, passes: u32| {
+                // Checkpoints are only allowed together with an output file.
+                let Some(path) = &options.output else {
+                    return;
+                };
+                match save(path, image, &transform) {
+                    Ok(()) => eprintln!("Saved checkpoint after {} passes", passes),
+                    Err(err) => eprintln!("failed to write checkpoint: {}", err),
+                }
+            };
+            render_progressive(
+                &camera,
+                world.as_ref(),
+                &scene.lights,
+                pattern.as_ref(),
+                &settings,
+                progressive,
+                &mut checkpoint,
+            )
         }
-        let sqrt = discriminant.sqrt();
+        None => render(
+            &camera,
+            world.as_ref(),
+            &scene.lights,
+            pattern.as_ref(),
+            &settings,
+        ),
+    };
 
-        let root = (-half_b - sqrt) / a;
-        if root < t_min || t_max < root {
-            let root = (-half_b + sqrt) / a;
-            if root < t_min || t_max < root {
-                return false;
-            }
+    let result = match &options.output {
+        Some(path) => save(path, &image, &transform),
+        None => {
+            let mut out = BufWriter::new(io::stdout().lock());
+            output::write(&mut out, Format::PpmAscii, &image, &transform)
         }
-        rec.t = root;
-        rec.p = ray.at(rec.t);
-        let outward_normal = (rec.p - self.center) / self.radius;
-        rec.set_face_normal(ray, outward_normal);
-
-        return true;
+    };
+    if let Err(err) = result {
+        eprintln!("failed to write image: {}", err);
+        process::exit(1);
     }
-}
 
-struct HittableList {
-    objects: Vec<Box<dyn Hittable>>,
+    eprintln!("\nDone.");
 }
 
-impl Hittable for HittableList {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
-        let mut temp_rec = HitRecord {
-            p: DVec3::new(0.0, 0.0, 0.0),
-            normal: DVec3::new(0.0, 0.0, 0.0),
-            t: 0.0,
-            front_face: false,
-        };
-        let mut hit_anything = false;
-        let mut closest_so_far = t_max;
-
-        for object in self.objects.iter() {
-            if object.hit(ray, t_min, closest_so_far, &mut temp_rec) {
-                hit_anything = true;
-                closest_so_far = temp_rec.t;
-                rec.p = temp_rec.p;
-                rec.normal = temp_rec.normal;
-                rec.t = temp_rec.t;
-                rec.front_face = temp_rec.front_face;
-            }
-        }
-
-        return hit_anything;
-    }
+/// Writes the image to a temporary file next to `path` and then moves it into
+/// place, so that stopping the program while it writes never leaves a truncated
+/// image behind, nor destroys the last checkpoint.
+fn save(path: &Path, image: &Image, transform: &OutputTransform) -> io::Result<()> {
+    // Keep the extension, which selects the format.
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    let partial = path.with_extension(format!("partial.{}", extension));
+    output::save(&partial, image, transform)?;
+    return fs::rename(&partial, path);
 }
@@ -0,0 +1,333 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use glam::DVec3;
+
+use crate::hittable::HitRecord;
+use crate::onb::Onb;
+use crate::random::{random_unit_vector, Rng};
+use crate::ray::Ray;
+use crate::texture::{SolidColor, Texture};
+
+/// Describes how light interacts with a surface.
+pub trait Material: Send + Sync {
+    /// Scatters `ray_in` at the hit described by `rec`. Returns false when the ray is
+    /// absorbed, otherwise fills in the attenuation and the scattered ray.
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        rng: &mut Rng,
+        attenuation: &mut DVec3,
+        scattered: &mut Ray,
+    ) -> bool;
+
+    /// Density, per unit solid angle, with which `scatter` picks `scattered`. Zero
+    /// (the default) marks materials such as mirrors and glass whose directions
+    /// cannot be chosen by sampling lights. For the others, attenuation times this
+    /// density must equal the BSDF times the cosine term, so that light samples
+    /// can be weighted with it.
+    fn scattering_pdf(&self, _ray_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        return 0.0;
+    }
+
+    /// Radiance the surface gives off at the hit described by `rec`.
+    fn emitted(&self, _rec: &HitRecord) -> DVec3 {
+        return DVec3::ZERO;
+    }
+}
+
+/// Ideal diffuse reflector.
+pub struct Lambertian {
+    pub albedo: Arc<dyn Texture>,
+}
+
+/// Mirror-like reflector; `fuzz` in [0, 1] perturbs the reflected direction.
+pub struct Metal {
+    pub albedo: DVec3,
+    pub fuzz: f64,
+}
+
+/// Clear refractive material such as glass or water.
+pub struct Dielectric {
+    /// Index of refraction relative to the enclosing medium.
+    pub refraction_index: f64,
+}
+
+/// Light source that emits `emit` and absorbs all incoming light. Unless
+/// `two_sided` is set, only the front of the surface (the side the outward normal
+/// points to) emits.
+pub struct DiffuseLight {
+    pub emit: Arc<dyn Texture>,
+    pub two_sided: bool,
+}
+
+/// Phase function of a participating medium that scatters equally in all
+/// directions.
+pub struct Isotropic {
+    pub albedo: Arc<dyn Texture>,
+}
+
+/// Henyey-Greenstein phase function. Asymmetry `g` in (-1, 1) goes from back
+/// scattering through isotropic (0) to forward scattering, as in haze or smoke.
+pub struct HenyeyGreenstein {
+    pub albedo: Arc<dyn Texture>,
+    pub g: f64,
+}
+
+impl Lambertian {
+    /// Diffuse reflector with the same color everywhere.
+    pub fn new(albedo: DVec3) -> Lambertian {
+        return Lambertian {
+            albedo: Arc::new(SolidColor { color: albedo }),
+        };
+    }
+}
+
+impl Material for Lambertian {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        rng: &mut Rng,
+        attenuation: &mut DVec3,
+        scattered: &mut Ray,
+    ) -> bool {
+        let mut scatter_direction = rec.normal + random_unit_vector(rng);
+        if near_zero(scatter_direction) {
+            scatter_direction = rec.normal;
+        }
+        *scattered = Ray {
+            origin: rec.p,
+            dir: scatter_direction,
+            time: ray_in.time,
+        };
+        *attenuation = self.albedo.value(rec.u, rec.v, rec.p);
+        return true;
+    }
+
+    /// Cosine-weighted hemisphere, which is what `normal + random_unit_vector` gives.
+    fn scattering_pdf(&self, _ray_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cosine = rec.normal.dot(scattered.dir.normalize());
+        return cosine.max(0.0) / PI;
+    }
+}
+
+impl Material for Metal {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        rng: &mut Rng,
+        attenuation: &mut DVec3,
+        scattered: &mut Ray,
+    ) -> bool {
+        let reflected = reflect(ray_in.dir.normalize(), rec.normal);
+        *scattered = Ray {
+            origin: rec.p,
+            dir: reflected + self.fuzz.clamp(0.0, 1.0) * random_unit_vector(rng),
+            time: ray_in.time,
+        };
+        *attenuation = self.albedo;
+        return scattered.dir.dot(rec.normal) > 0.0;
+    }
+}
+
+impl Material for Dielectric {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        rng: &mut Rng,
+        attenuation: &mut DVec3,
+        scattered: &mut Ray,
+    ) -> bool {
+        let ri = if rec.front_face {
+            1.0 / self.refraction_index
+        } else {
+            self.refraction_index
+        };
+
+        let unit_direction = ray_in.dir.normalize();
+        let cos_theta = (-unit_direction).dot(rec.normal).min(1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+
+        let cannot_refract = ri * sin_theta > 1.0;
+        let direction = if cannot_refract || reflectance(cos_theta, ri) > rng.next_f64() {
+            reflect(unit_direction, rec.normal)
+        } else {
+            refract(unit_direction, rec.normal, ri)
+        };
+
+        *scattered = Ray {
+            origin: rec.p,
+            dir: direction,
+            time: ray_in.time,
+        };
+        *attenuation = DVec3::new(1.0, 1.0, 1.0);
+        return true;
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _ray_in: &Ray,
+        _rec: &HitRecord,
+        _rng: &mut Rng,
+        _attenuation: &mut DVec3,
+        _scattered: &mut Ray,
+    ) -> bool {
+        return false;
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> DVec3 {
+        if !rec.front_face && !self.two_sided {
+            return DVec3::ZERO;
+        }
+        return self.emit.value(rec.u, rec.v, rec.p);
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        rng: &mut Rng,
+        attenuation: &mut DVec3,
+        scattered: &mut Ray,
+    ) -> bool {
+        *scattered = Ray {
+            origin: rec.p,
+            dir: random_unit_vector(rng),
+            time: ray_in.time,
+        };
+        *attenuation = self.albedo.value(rec.u, rec.v, rec.p);
+        return true;
+    }
+
+    fn scattering_pdf(&self, _ray_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        return 1.0 / (4.0 * PI);
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        rng: &mut Rng,
+        attenuation: &mut DVec3,
+        scattered: &mut Ray,
+    ) -> bool {
+        let g = self.g;
+        let xi = rng.next_f64();
+        // Inverse of the cumulative distribution of the cosine to the incoming direction.
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * xi
+        } else {
+            let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+            ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+        };
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * rng.next_f64();
+        let local = DVec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta);
+        *scattered = Ray {
+            origin: rec.p,
+            dir: Onb::from_w(ray_in.dir).to_world(local),
+            time: ray_in.time,
+        };
+        *attenuation = self.albedo.value(rec.u, rec.v, rec.p);
+        return true;
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, _rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cos_theta = ray_in.dir.normalize().dot(scattered.dir.normalize());
+        let denom = 1.0 + self.g * self.g - 2.0 * self.g * cos_theta;
+        return (1.0 - self.g * self.g) / (4.0 * PI * denom * denom.sqrt());
+    }
+}
+
+fn near_zero(v: DVec3) -> bool {
+    let s = 1e-8;
+    return v.x.abs() < s && v.y.abs() < s && v.z.abs() < s;
+}
+
+fn reflect(v: DVec3, n: DVec3) -> DVec3 {
+    return v - 2.0 * v.dot(n) * n;
+}
+
+/// Snell's law for a unit direction `uv` entering a surface with unit normal `n`.
+fn refract(uv: DVec3, n: DVec3, etai_over_etat: f64) -> DVec3 {
+    let cos_theta = (-uv).dot(n).min(1.0);
+    let r_out_perp = etai_over_etat * (uv + cos_theta * n);
+    let r_out_parallel = -(1.0 - r_out_perp.length_squared()).abs().sqrt() * n;
+    return r_out_perp + r_out_parallel;
+}
+
+/// Schlick's approximation of the Fresnel reflectance.
+fn reflectance(cosine: f64, refraction_index: f64) -> f64 {
+    let r0 = (1.0 - refraction_index) / (1.0 + refraction_index);
+    let r0 = r0 * r0;
+    return r0 + (1.0 - r0) * (1.0 - cosine).powi(5);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture::SolidColor;
+
+    fn record() -> HitRecord {
+        return HitRecord {
+            p: DVec3::ZERO,
+            normal: DVec3::Y,
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: true,
+            material: None,
+        };
+    }
+
+    #[test]
+    fn henyey_greenstein_samples_match_its_density() {
+        for g in [-0.5, 0.0, 0.7] {
+            let phase = HenyeyGreenstein {
+                albedo: Arc::new(SolidColor { color: DVec3::ONE }),
+                g,
+            };
+            let ray_in = Ray {
+                origin: DVec3::ZERO,
+                dir: DVec3::new(1.0, 2.0, 0.5),
+                time: 0.0,
+            };
+            let mut rng = Rng::new(11);
+            let mut attenuation = DVec3::ZERO;
+            let mut scattered = ray_in;
+            let count = 50_000;
+            let mut mean_cosine = 0.0;
+            let mut density_integral = 0.0;
+            for _ in 0..count {
+                phase.scatter(
+                    &ray_in,
+                    &record(),
+                    &mut rng,
+                    &mut attenuation,
+                    &mut scattered,
+                );
+                mean_cosine += ray_in.dir.normalize().dot(scattered.dir.normalize());
+                // Integrate the density over uniformly distributed directions.
+                let uniform = Ray {
+                    dir: random_unit_vector(&mut rng),
+                    ..ray_in
+                };
+                density_integral += phase.scattering_pdf(&ray_in, &record(), &uniform);
+            }
+            // The mean cosine of Henyey-Greenstein scattering is g.
+            assert!((mean_cosine / count as f64 - g).abs() < 0.01, "g = {}", g);
+            let integral = 4.0 * PI * density_integral / count as f64;
+            assert!((integral - 1.0).abs() < 0.05, "g = {}: {}", g, integral);
+        }
+    }
+}
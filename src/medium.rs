@@ -0,0 +1,593 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use glam::DVec3;
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::random::{mix, Rng};
+use crate::ray::Ray;
+
+/// Homogeneous participating medium such as fog or smoke filling a closed, possibly
+/// concave `boundary`. Rays travelling through it scatter after an exponentially
+/// distributed distance; where they do, `phase_function` decides the new direction.
+pub struct ConstantMedium {
+    pub boundary: Arc<dyn Hittable>,
+    /// Scattering events per unit of distance.
+    pub density: f64,
+    pub phase_function: Arc<dyn Material>,
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let ray_length = ray.dir.length();
+        // `hit` has no random generator, so the free-flight distance is drawn from a
+        // stream derived from the ray itself, which keeps renders reproducible.
+        let mut rng = Rng::new(ray_seed(ray));
+        let mut remaining = -(1.0 - rng.next_f64()).ln() / self.density;
+
+        // A concave boundary can be entered several times; the distance is spent
+        // over all the spans inside it in turn.
+        let mut after = f64::NEG_INFINITY;
+        loop {
+            let Some((enter, exit)) = boundary_span(self.boundary.as_ref(), ray, after) else {
+                return false;
+            };
+            let (enter, clipped_exit) = (enter.max(t_min), exit.min(t_max));
+            if enter < clipped_exit {
+                let distance_inside = (clipped_exit - enter) * ray_length;
+                if remaining <= distance_inside {
+                    rec.t = enter + remaining / ray_length;
+                    break;
+                }
+                remaining -= distance_inside;
+            }
+            if exit >= t_max {
+                return false;
+            }
+            after = exit;
+        }
+
+        rec.p = ray.at(rec.t);
+        // Scattering inside a volume has no surface, so these are arbitrary.
+        rec.normal = DVec3::X;
+        rec.front_face = true;
+        rec.u = 0.0;
+        rec.v = 0.0;
+        rec.material = Some(self.phase_function.clone());
+        return true;
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        return self.boundary.bounding_box();
+    }
+}
+
+/// Values on a regular 3D lattice, stored with x varying fastest, then y, then z.
+pub struct VoxelGrid {
+    pub resolution: [usize; 3],
+    pub values: Vec<f32>,
+}
+
+impl VoxelGrid {
+    /// Reads a raw grid file; see [`VoxelGrid::from_bytes`].
+    pub fn load(path: &Path, resolution: [usize; 3]) -> io::Result<VoxelGrid> {
+        // Checked before reading, so that a wrong or huge file is never loaded whole.
+        check_size(fs::metadata(path)?.len(), resolution)?;
+        return VoxelGrid::from_bytes(&fs::read(path)?, resolution);
+    }
+
+    /// Decodes headerless little-endian 32-bit floats, which must be finite and
+    /// non-negative and exactly fill the given resolution of at least one voxel.
+    pub fn from_bytes(bytes: &[u8], resolution: [usize; 3]) -> io::Result<VoxelGrid> {
+        check_size(bytes.len() as u64, resolution)?;
+        let invalid =
+            |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let values: Vec<f32> = bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        if values
+            .iter()
+            .any(|value| !value.is_finite() || *value < 0.0)
+        {
+            return Err(invalid("grid values must be finite and non-negative"));
+        }
+        return Ok(VoxelGrid { resolution, values });
+    }
+
+    /// Largest value in the grid, which bounds every interpolated value.
+    pub fn max(&self) -> f64 {
+        return self
+            .values
+            .iter()
+            .fold(0.0f32, |max, &value| max.max(value)) as f64;
+    }
+
+    /// Trilinear interpolation at `p` in [0, 1]^3, where voxel centers lie at
+    /// (i + 0.5) / resolution. Values beyond the outer centers are held constant.
+    pub fn sample(&self, p: DVec3) -> f64 {
+        let mut lower = [0; 3];
+        let mut frac = [0.0; 3];
+        for axis in 0..3 {
+            let n = self.resolution[axis];
+            let x = (p[axis] * n as f64 - 0.5).clamp(0.0, (n - 1) as f64);
+            lower[axis] = (x as usize).min(n.saturating_sub(2));
+            frac[axis] = x - lower[axis] as f64;
+        }
+        let mut accum = 0.0;
+        for corner in 0..8 {
+            let mut index = 0;
+            let mut weight = 1.0;
+            for axis in (0..3).rev() {
+                let offset = (corner >> axis) & 1;
+                let i = (lower[axis] + offset).min(self.resolution[axis] - 1);
+                index = index * self.resolution[axis] + i;
+                weight *= if offset == 1 {
+                    frac[axis]
+                } else {
+                    1.0 - frac[axis]
+                };
+            }
+            if weight > 0.0 {
+                accum += weight * self.values[index] as f64;
+            }
+        }
+        return accum;
+    }
+}
+
+/// Checks that `found` bytes are exactly the 32-bit floats of a grid of `resolution`.
+fn check_size(found: u64, resolution: [usize; 3]) -> io::Result<()> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let size = resolution
+        .iter()
+        .try_fold(4usize, |size, &n| size.checked_mul(n))
+        .filter(|&size| size > 0)
+        .ok_or_else(|| invalid("grid resolution must be positive and not too large".to_string()))?;
+    if found != size as u64 {
+        return Err(invalid(format!(
+            "expected {} bytes for a {}x{}x{} grid, found {}",
+            size, resolution[0], resolution[1], resolution[2], found
+        )));
+    }
+    return Ok(());
+}
+
+/// Heterogeneous participating medium, such as smoke or fire from a simulation,
+/// whose coefficients come from voxel grids stretched over `bounds`. The two grids
+/// may be the same density grid, which gives the whole volume one albedo.
+pub struct GridVolume {
+    pub bounds: Aabb,
+    /// Per-voxel factor of `absorption`.
+    pub absorption_grid: Arc<VoxelGrid>,
+    /// Absorption per unit of distance where the grid is 1.
+    pub absorption: f64,
+    /// Per-voxel factor of `scattering`.
+    pub scattering_grid: Arc<VoxelGrid>,
+    /// Scattering per unit of distance where the grid is 1.
+    pub scattering: f64,
+    /// Grid over the same bounds scaling `emission_color`; the medium emits where
+    /// it absorbs, so emission needs `absorption` above zero.
+    pub emission: Option<VoxelGrid>,
+    pub emission_color: DVec3,
+    pub phase_function: Arc<dyn Material>,
+}
+
+impl GridVolume {
+    /// Position of `p` within the bounds, in [0, 1]^3 inside the volume.
+    fn local(&self, p: DVec3) -> DVec3 {
+        return (p - self.bounds.min) / (self.bounds.max - self.bounds.min);
+    }
+
+    /// Upper bound of the extinction coefficient anywhere in the volume. Delta
+    /// tracking needs it to be finite.
+    pub fn majorant(&self) -> f64 {
+        return self.absorption * self.absorption_grid.max()
+            + self.scattering * self.scattering_grid.max();
+    }
+
+    /// Absorption and scattering coefficients at `p`.
+    fn coefficients(&self, p: DVec3) -> (f64, f64) {
+        let local = self.local(p);
+        let absorption = self.absorption_grid.sample(local);
+        let scattering = if Arc::ptr_eq(&self.absorption_grid, &self.scattering_grid) {
+            absorption
+        } else {
+            self.scattering_grid.sample(local)
+        };
+        return (self.absorption * absorption, self.scattering * scattering);
+    }
+}
+
+/// Collisions inside a [`GridVolume`] scatter by its phase function weighted with
+/// the local scattering albedo, and emit the absorbed fraction of the local emission.
+impl Material for GridVolume {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        rng: &mut Rng,
+        attenuation: &mut DVec3,
+        scattered: &mut Ray,
+    ) -> bool {
+        let (absorption, scattering) = self.coefficients(rec.p);
+        if scattering <= 0.0 {
+            return false;
+        }
+        if !self
+            .phase_function
+            .scatter(ray_in, rec, rng, attenuation, scattered)
+        {
+            return false;
+        }
+        *attenuation *= scattering / (absorption + scattering);
+        return true;
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        return self.phase_function.scattering_pdf(ray_in, rec, scattered);
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> DVec3 {
+        let Some(emission) = &self.emission else {
+            return DVec3::ZERO;
+        };
+        let (absorption, scattering) = self.coefficients(rec.p);
+        let extinction = absorption + scattering;
+        if extinction <= 0.0 {
+            return DVec3::ZERO;
+        }
+        return absorption / extinction * emission.sample(self.local(rec.p)) * self.emission_color;
+    }
+}
+
+/// [`GridVolume`] placed in the scene. Collisions are found by delta tracking
+/// against the largest extinction in the grid.
+pub struct GridMedium {
+    volume: Arc<GridVolume>,
+    /// Upper bound of the extinction coefficient anywhere in the volume.
+    majorant: f64,
+}
+
+impl GridMedium {
+    /// Panics if the volume's majorant is not finite, as delta tracking would then
+    /// never advance.
+    pub fn new(volume: GridVolume) -> GridMedium {
+        let majorant = volume.majorant();
+        assert!(majorant.is_finite(), "volume extinction must be finite");
+        return GridMedium {
+            volume: Arc::new(volume),
+            majorant,
+        };
+    }
+}
+
+impl Hittable for GridMedium {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        if self.majorant <= 0.0 {
+            return false;
+        }
+        let Some((enter, exit)) = self.volume.bounds.span(ray, t_min, t_max) else {
+            return false;
+        };
+
+        // Sample tentative collisions as if the whole volume had the majorant's
+        // extinction, and accept each with the ratio of the real extinction to it.
+        let majorant = self.majorant * ray.dir.length();
+        let mut rng = Rng::new(ray_seed(ray));
+        let mut t = enter;
+        loop {
+            t -= (1.0 - rng.next_f64()).ln() / majorant;
+            if t >= exit {
+                return false;
+            }
+            let p = ray.at(t);
+            let (absorption, scattering) = self.volume.coefficients(p);
+            if rng.next_f64() * self.majorant < absorption + scattering {
+                rec.t = t;
+                rec.p = p;
+                rec.normal = DVec3::X;
+                rec.front_face = true;
+                rec.u = 0.0;
+                rec.v = 0.0;
+                rec.material = Some(self.volume.clone());
+                return true;
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        return self.volume.bounds;
+    }
+}
+
+/// Parameters where `ray` next enters and then leaves the closed `boundary` after
+/// `after`. Rays starting inside enter behind their origin.
+fn boundary_span(boundary: &dyn Hittable, ray: &Ray, after: f64) -> Option<(f64, f64)> {
+    let mut rec = HitRecord {
+        p: DVec3::ZERO,
+        normal: DVec3::ZERO,
+        t: 0.0,
+        u: 0.0,
+        v: 0.0,
+        front_face: false,
+        material: None,
+    };
+    if !boundary.hit(ray, after + 0.0001, f64::INFINITY, &mut rec) {
+        return None;
+    }
+    let enter = rec.t;
+    if !boundary.hit(ray, enter + 0.0001, f64::INFINITY, &mut rec) {
+        return None;
+    }
+    return Some((enter, rec.t));
+}
+
+/// Seed that differs between rays, including rays that differ only in time.
+fn ray_seed(ray: &Ray) -> u64 {
+    let values = [
+        ray.origin.x,
+        ray.origin.y,
+        ray.origin.z,
+        ray.dir.x,
+        ray.dir.y,
+        ray.dir.z,
+        ray.time,
+    ];
+    return values
+        .iter()
+        .fold(0, |seed, value| mix(seed, value.to_bits()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cuboid::Cuboid;
+    use crate::material::{Isotropic, Lambertian};
+    use crate::texture::SolidColor;
+    use crate::torus::Torus;
+
+    fn record() -> HitRecord {
+        return HitRecord {
+            p: DVec3::ZERO,
+            normal: DVec3::ZERO,
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: false,
+            material: None,
+        };
+    }
+
+    fn fog(density: f64) -> ConstantMedium {
+        return ConstantMedium {
+            boundary: Arc::new(Cuboid {
+                min: DVec3::splat(-1.0),
+                max: DVec3::splat(1.0),
+                material: Arc::new(Lambertian::new(DVec3::ONE)),
+            }),
+            density,
+            phase_function: Arc::new(Isotropic {
+                albedo: Arc::new(SolidColor { color: DVec3::ONE }),
+            }),
+        };
+    }
+
+    #[test]
+    fn transmittance_follows_beer_lambert() {
+        // A path of length 2 through density 0.5 passes with probability e^-1.
+        let medium = fog(0.5);
+        let mut rng = Rng::new(9);
+        let count = 20_000;
+        let mut passed = 0;
+        for _ in 0..count {
+            let ray = Ray {
+                origin: DVec3::new(rng.next_f64() - 0.5, rng.next_f64() - 0.5, 5.0),
+                dir: DVec3::new(0.0, 0.0, -0.5 - rng.next_f64()),
+                time: 0.0,
+            };
+            let mut rec = record();
+            if medium.hit(&ray, 0.001, f64::INFINITY, &mut rec) {
+                assert!(rec.p.z <= 1.0 + 1e-9 && rec.p.z >= -1.0 - 1e-9);
+            } else {
+                passed += 1;
+            }
+        }
+        let fraction = passed as f64 / count as f64;
+        assert!((fraction - (-1.0f64).exp()).abs() < 0.015, "{}", fraction);
+    }
+
+    #[test]
+    fn rays_starting_inside_scatter_after_their_origin() {
+        let medium = fog(100.0);
+        let ray = Ray {
+            origin: DVec3::ZERO,
+            dir: DVec3::X,
+            time: 0.0,
+        };
+        let mut rec = record();
+        assert!(medium.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert!(rec.t >= 0.001 && rec.t < 1.0);
+        let mut again = record();
+        assert!(medium.hit(&ray, 0.001, f64::INFINITY, &mut again));
+        assert_eq!(rec.t, again.t);
+        // The same ray always draws the same distance, which is beyond a shorter span.
+        assert!(!medium.hit(&ray, 0.001, rec.t * 0.5, &mut again));
+    }
+
+    #[test]
+    fn concave_boundaries_count_every_span_inside() {
+        // Through both sides of the tube: two spans of length 1 at density 0.5.
+        let medium = ConstantMedium {
+            boundary: Arc::new(Torus {
+                center: DVec3::ZERO,
+                axis: DVec3::Y,
+                major_radius: 2.0,
+                minor_radius: 0.5,
+                material: Arc::new(Lambertian::new(DVec3::ONE)),
+            }),
+            ..fog(0.5)
+        };
+        let mut rng = Rng::new(10);
+        let count = 20_000;
+        let mut passed = 0;
+        for _ in 0..count {
+            let ray = Ray {
+                origin: DVec3::new(-5.0, 0.0, 0.0),
+                dir: DVec3::new(0.5 + rng.next_f64(), 0.0, 0.0),
+                time: rng.next_f64(),
+            };
+            let mut rec = record();
+            if medium.hit(&ray, 0.001, f64::INFINITY, &mut rec) {
+                // Never inside the hole of the torus.
+                assert!(rec.p.x.abs() >= 1.5 - 1e-9, "{}", rec.p.x);
+            } else {
+                passed += 1;
+            }
+        }
+        let fraction = passed as f64 / count as f64;
+        assert!((fraction - (-1.0f64).exp()).abs() < 0.015, "{}", fraction);
+    }
+
+    fn grid(resolution: [usize; 3], values: &[f32]) -> VoxelGrid {
+        let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        return VoxelGrid::from_bytes(&bytes, resolution).unwrap();
+    }
+
+    fn smoke(density: VoxelGrid, scattering: f64) -> GridMedium {
+        let density = Arc::new(density);
+        return GridMedium::new(GridVolume {
+            bounds: Aabb::new(DVec3::splat(-1.0), DVec3::splat(1.0)),
+            absorption_grid: density.clone(),
+            absorption: 0.0,
+            scattering_grid: density,
+            scattering,
+            emission: None,
+            emission_color: DVec3::ZERO,
+            phase_function: Arc::new(Isotropic {
+                albedo: Arc::new(SolidColor { color: DVec3::ONE }),
+            }),
+        });
+    }
+
+    #[test]
+    fn voxel_grid_decodes_and_interpolates() {
+        let g = grid([2, 1, 1], &[1.0, 3.0]);
+        assert_eq!(g.max(), 3.0);
+        // Voxel centers at x = 0.25 and 0.75, constant beyond them.
+        assert_eq!(g.sample(DVec3::new(0.25, 0.5, 0.5)), 1.0);
+        assert_eq!(g.sample(DVec3::new(0.5, 0.5, 0.5)), 2.0);
+        assert_eq!(g.sample(DVec3::new(1.0, 0.0, 1.0)), 3.0);
+        let g = grid([1, 2, 2], &[0.0, 1.0, 2.0, 3.0]);
+        // Index order is x fastest, then y, then z.
+        assert_eq!(g.sample(DVec3::new(0.5, 0.75, 0.25)), 1.0);
+        assert_eq!(g.sample(DVec3::new(0.5, 0.25, 0.75)), 2.0);
+
+        assert!(VoxelGrid::from_bytes(&[0; 12], [2, 2, 1]).is_err());
+        assert!(VoxelGrid::from_bytes(&[], [0, 2, 1]).is_err());
+        assert!(VoxelGrid::from_bytes(&[0; 4], [1 << 40, 1 << 40, 1]).is_err());
+        assert!(VoxelGrid::from_bytes(&(-1.0f32).to_le_bytes(), [1, 1, 1]).is_err());
+    }
+
+    #[test]
+    fn grid_files_of_the_wrong_size_are_rejected_before_reading() {
+        let path = std::env::temp_dir().join(format!("medium-test-{}.raw", std::process::id()));
+        let file = fs::File::create(&path).unwrap();
+        // Sparse, so it takes no space on disk but could not be read into memory.
+        file.set_len(1 << 40).unwrap();
+        let result = VoxelGrid::load(&path, [2, 2, 2]);
+        file.set_len(32).unwrap();
+        let grid = VoxelGrid::load(&path, [2, 2, 2]);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "expected 32 bytes for a 2x2x2 grid, found 1099511627776"
+        );
+        assert_eq!(grid.unwrap().max(), 0.0);
+    }
+
+    #[test]
+    fn delta_tracking_follows_beer_lambert() {
+        // Half the majorant everywhere: a path of length 2 at extinction 0.5.
+        let medium = smoke(
+            grid([2, 2, 2], &[0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 1.0]),
+            1.0,
+        );
+        let mut rng = Rng::new(5);
+        let count = 20_000;
+        let mut passed = 0;
+        for _ in 0..count {
+            let ray = Ray {
+                origin: DVec3::new(-0.5 - 0.4 * rng.next_f64(), rng.next_f64() - 0.5, 5.0),
+                dir: DVec3::new(0.0, 0.0, -0.5 - rng.next_f64()),
+                time: 0.0,
+            };
+            if !medium.hit(&ray, 0.001, f64::INFINITY, &mut record()) {
+                passed += 1;
+            }
+        }
+        let fraction = passed as f64 / count as f64;
+        assert!((fraction - (-1.0f64).exp()).abs() < 0.015, "{}", fraction);
+    }
+
+    #[test]
+    fn albedo_and_emission_vary_per_voxel() {
+        // Absorbing and glowing at -x, purely scattering at +x.
+        let volume = GridVolume {
+            bounds: Aabb::new(DVec3::splat(-1.0), DVec3::splat(1.0)),
+            absorption_grid: Arc::new(grid([2, 1, 1], &[1.0, 0.0])),
+            absorption: 2.0,
+            scattering_grid: Arc::new(grid([2, 1, 1], &[0.0, 1.0])),
+            scattering: 3.0,
+            emission: Some(grid([1, 1, 1], &[1.0])),
+            emission_color: DVec3::new(4.0, 2.0, 1.0),
+            phase_function: Arc::new(Isotropic {
+                albedo: Arc::new(SolidColor { color: DVec3::ONE }),
+            }),
+        };
+        assert_eq!(volume.majorant(), 5.0);
+        let ray = Ray {
+            origin: DVec3::ZERO,
+            dir: DVec3::X,
+            time: 0.0,
+        };
+        let mut rng = Rng::new(1);
+        let (mut attenuation, mut scattered) = (DVec3::ZERO, ray);
+        let mut at = |x: f64| {
+            let rec = HitRecord {
+                p: DVec3::new(x, 0.0, 0.0),
+                ..record()
+            };
+            let scatters = volume.scatter(&ray, &rec, &mut rng, &mut attenuation, &mut scattered);
+            return (scatters, attenuation, volume.emitted(&rec));
+        };
+        assert_eq!(at(-0.75), (false, DVec3::ZERO, DVec3::new(4.0, 2.0, 1.0)));
+        assert_eq!(at(0.75), (true, DVec3::ONE, DVec3::ZERO));
+        // Halfway between the voxel centers: extinction 1 + 1.5, albedo 1.5 / 2.5.
+        let (scatters, attenuation, emitted) = at(0.0);
+        assert!(scatters);
+        assert!((attenuation - DVec3::splat(0.6)).length() < 1e-12);
+        assert!((emitted - DVec3::new(1.6, 0.8, 0.4)).length() < 1e-12);
+    }
+
+    #[test]
+    fn empty_voxels_never_collide() {
+        let medium = smoke(grid([2, 1, 1], &[0.0, 1.0]), 50.0);
+        let mut rng = Rng::new(6);
+        for _ in 0..1000 {
+            let ray = Ray {
+                origin: DVec3::new(-5.0, rng.next_f64() - 0.5, rng.next_f64() - 0.5),
+                dir: DVec3::X,
+                time: 0.0,
+            };
+            let mut rec = record();
+            assert!(medium.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+            // Density only rises above zero past the center of the first voxel.
+            assert!(rec.p.x > -0.5, "{}", rec.p.x);
+        }
+    }
+}
@@ -0,0 +1,590 @@
+//! Wavefront OBJ and MTL import.
+//!
+//! Supports vertices, texture coordinates, normals, polygonal faces (triangulated as
+//! fans, so they are expected to be convex), groups/objects and `usemtl`/`mtllib`.
+//! Of the material statements, diffuse image maps (`map_Kd`) and emission (`Ke`)
+//! are also read. Other statements are ignored. Faces are collected into one
+//! [`TriangleMesh`] per group and material.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use glam::{DVec2, DVec3};
+
+use crate::hittable::Hittable;
+use crate::input;
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::texture::{ImageTexture, SolidColor, Texture};
+use crate::triangle::TriangleMesh;
+
+/// Meshes read from an OBJ file.
+pub struct ObjModel {
+    pub meshes: Vec<ObjMesh>,
+}
+
+/// Faces of one group sharing one material.
+pub struct ObjMesh {
+    /// Name from the last `g` or `o` statement, empty if there was none.
+    pub group: String,
+    pub mesh: Arc<TriangleMesh>,
+}
+
+/// Error in an OBJ or MTL file.
+#[derive(Debug)]
+pub struct ObjError {
+    pub path: PathBuf,
+    /// 1-based line number, or 0 if the error concerns the whole file.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            return write!(f, "{}: {}", self.path.display(), self.message);
+        }
+        return write!(f, "{}:{}: {}", self.path.display(), self.line, self.message);
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+impl ObjModel {
+    /// One object per triangle, ready to be added to a `HittableList` or BVH.
+    pub fn into_hittables(self) -> Vec<Box<dyn Hittable>> {
+        return self
+            .meshes
+            .iter()
+            .flat_map(|m| TriangleMesh::triangles(&m.mesh))
+            .collect();
+    }
+}
+
+/// Loads the OBJ file at `path` together with the material libraries it references.
+/// Faces without a known material use `default_material`.
+pub fn load_obj(path: &Path, default_material: Arc<dyn Material>) -> Result<ObjModel, ObjError> {
+    let source = fs::read_to_string(path).map_err(|err| ObjError {
+        path: path.to_path_buf(),
+        line: 0,
+        message: err.to_string(),
+    })?;
+    return parse_obj(&source, path, default_material);
+}
+
+/// Parses OBJ text. `path` is used in error messages and to resolve `mtllib`
+/// statements relative to the file.
+pub fn parse_obj(
+    source: &str,
+    path: &Path,
+    default_material: Arc<dyn Material>,
+) -> Result<ObjModel, ObjError> {
+    let mut builder = ObjBuilder {
+        positions: Vec::new(),
+        uvs: Vec::new(),
+        normals: Vec::new(),
+        materials: HashMap::new(),
+        group: String::new(),
+        material_name: String::new(),
+        meshes: Vec::new(),
+    };
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+
+    for (index, line) in source.lines().enumerate() {
+        let error = |message: String| ObjError {
+            path: path.to_path_buf(),
+            line: index + 1,
+            message,
+        };
+        let line = line.split('#').next().unwrap_or("");
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => builder.positions.push(parse_vec3(&args).map_err(error)?),
+            "vn" => builder.normals.push(parse_vec3(&args).map_err(error)?),
+            "vt" => {
+                let u = parse_number(args.first().copied()).map_err(error)?;
+                let v = match args.get(1) {
+                    Some(v) => parse_number(Some(v)).map_err(error)?,
+                    None => 0.0,
+                };
+                builder.uvs.push(DVec2::new(u, v));
+            }
+            "f" => builder.add_face(&args).map_err(error)?,
+            "g" | "o" => builder.group = args.join(" "),
+            "usemtl" => builder.material_name = args.join(" "),
+            "mtllib" => {
+                for library in args {
+                    let library_path = base_dir.join(library);
+                    let source = fs::read_to_string(&library_path).map_err(|err| {
+                        error(format!(
+                            "failed to read '{}': {}",
+                            library_path.display(),
+                            err
+                        ))
+                    })?;
+                    builder.materials.extend(parse_mtl(&source, &library_path)?);
+                }
+            }
+            // Smoothing groups, lines, points, curves and the like are not supported.
+            _ => {}
+        }
+    }
+
+    let meshes = builder
+        .meshes
+        .into_iter()
+        .map(|pending| {
+            let material = builder
+                .materials
+                .get(&pending.material_name)
+                .cloned()
+                .unwrap_or_else(|| default_material.clone());
+            let group = pending.group.clone();
+            return ObjMesh {
+                group,
+                mesh: Arc::new(pending.finish(material)),
+            };
+        })
+        .collect();
+    return Ok(ObjModel { meshes });
+}
+
+/// Parses an MTL material library, mapping every material onto the closest
+/// renderer material:
+///
+/// * emissive materials (`Ke` above zero) become [`DiffuseLight`] emitting `Ke`,
+/// * transparent materials (`d` < 1, `Tr` > 0 or `illum` 4, 6, 7, 9) become
+///   [`Dielectric`] with index of refraction `Ni`,
+/// * reflective ones (`illum` 3, 5, 8) become [`Metal`] tinted by `Ks`, with a fuzz
+///   derived from the specular exponent `Ns`,
+/// * everything else becomes [`Lambertian`] with albedo `Kd`.
+pub fn parse_mtl(
+    source: &str,
+    path: &Path,
+) -> Result<HashMap<String, Arc<dyn Material>>, ObjError> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlDescription)> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let error = |message: String| ObjError {
+            path: path.to_path_buf(),
+            line: index + 1,
+            message,
+        };
+        let line = line.split('#').next().unwrap_or("");
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            if let Some((name, description)) = current.take() {
+                materials.insert(name, description.to_material());
+            }
+            current = Some((args.join(" "), MtlDescription::default()));
+            continue;
+        }
+        let Some((_, description)) = current.as_mut() else {
+            // Statements before the first `newmtl` have nothing to apply to.
+            continue;
+        };
+        match keyword {
+            "Kd" => description.diffuse = parse_vec3(&args).map_err(error)?,
+            "Ks" => description.specular = parse_vec3(&args).map_err(error)?,
+            "Ke" => description.emission = parse_vec3(&args).map_err(error)?,
+            "Ns" => description.shininess = parse_number(args.first().copied()).map_err(error)?,
+            "Ni" => description.ior = parse_number(args.first().copied()).map_err(error)?,
+            "d" => description.dissolve = parse_number(args.first().copied()).map_err(error)?,
+            "Tr" => {
+                description.dissolve = 1.0 - parse_number(args.first().copied()).map_err(error)?
+            }
+            "map_Kd" => {
+                // Options such as `-s` may precede the file name, which comes last.
+                let file = args
+                    .last()
+                    .ok_or_else(|| error("expected a texture file name".to_string()))?;
+                let image = input::load(&path.parent().unwrap_or(Path::new("")).join(file))
+                    .map_err(|err| error(format!("failed to load texture '{}': {}", file, err)))?;
+                description.diffuse_map = Some(Arc::new(ImageTexture { image }));
+            }
+            "illum" => {
+                description.illum = args
+                    .first()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| error("expected an illumination model number".to_string()))?
+            }
+            _ => {}
+        }
+    }
+    if let Some((name, description)) = current {
+        materials.insert(name, description.to_material());
+    }
+    return Ok(materials);
+}
+
+struct MtlDescription {
+    diffuse: DVec3,
+    specular: DVec3,
+    emission: DVec3,
+    shininess: f64,
+    ior: f64,
+    dissolve: f64,
+    illum: u32,
+    /// Replaces `diffuse` when present.
+    diffuse_map: Option<Arc<dyn Texture>>,
+}
+
+impl Default for MtlDescription {
+    fn default() -> Self {
+        return MtlDescription {
+            diffuse: DVec3::splat(0.8),
+            specular: DVec3::ZERO,
+            emission: DVec3::ZERO,
+            shininess: 0.0,
+            ior: 1.5,
+            dissolve: 1.0,
+            illum: 2,
+            diffuse_map: None,
+        };
+    }
+}
+
+impl MtlDescription {
+    fn to_material(&self) -> Arc<dyn Material> {
+        if self.emission.cmpgt(DVec3::ZERO).any() {
+            return Arc::new(DiffuseLight {
+                emit: Arc::new(SolidColor {
+                    color: self.emission,
+                }),
+                two_sided: false,
+            });
+        }
+        if self.dissolve < 1.0 || matches!(self.illum, 4 | 6 | 7 | 9) {
+            return Arc::new(Dielectric {
+                refraction_index: self.ior,
+            });
+        }
+        if matches!(self.illum, 3 | 5 | 8) {
+            return Arc::new(Metal {
+                albedo: self.specular,
+                // Common Phong exponent to roughness conversion.
+                fuzz: (2.0 / (self.shininess.max(0.0) + 2.0)).sqrt(),
+            });
+        }
+        return match &self.diffuse_map {
+            Some(texture) => Arc::new(Lambertian {
+                albedo: texture.clone(),
+            }),
+            None => Arc::new(Lambertian::new(self.diffuse)),
+        };
+    }
+}
+
+struct ObjBuilder {
+    positions: Vec<DVec3>,
+    uvs: Vec<DVec2>,
+    normals: Vec<DVec3>,
+    materials: HashMap<String, Arc<dyn Material>>,
+    group: String,
+    material_name: String,
+    meshes: Vec<PendingMesh>,
+}
+
+/// Mesh under construction. OBJ indexes positions, UVs and normals separately, so
+/// every distinct combination becomes one mesh vertex.
+struct PendingMesh {
+    group: String,
+    material_name: String,
+    corners: HashMap<(usize, Option<usize>, Option<usize>), usize>,
+    positions: Vec<DVec3>,
+    uvs: Vec<Option<DVec2>>,
+    normals: Vec<Option<DVec3>>,
+    indices: Vec<[usize; 3]>,
+}
+
+impl ObjBuilder {
+    fn add_face(&mut self, args: &[&str]) -> Result<(), String> {
+        if args.len() < 3 {
+            return Err(format!(
+                "face needs at least 3 vertices, found {}",
+                args.len()
+            ));
+        }
+        let mut corners = Vec::with_capacity(args.len());
+        for arg in args {
+            corners.push(self.parse_corner(arg)?);
+        }
+
+        let mesh_index = match self
+            .meshes
+            .iter()
+            .position(|m| m.group == self.group && m.material_name == self.material_name)
+        {
+            Some(index) => index,
+            None => {
+                self.meshes.push(PendingMesh {
+                    group: self.group.clone(),
+                    material_name: self.material_name.clone(),
+                    corners: HashMap::new(),
+                    positions: Vec::new(),
+                    uvs: Vec::new(),
+                    normals: Vec::new(),
+                    indices: Vec::new(),
+                });
+                self.meshes.len() - 1
+            }
+        };
+        let mesh = &mut self.meshes[mesh_index];
+
+        let vertices: Vec<usize> = corners
+            .into_iter()
+            .map(|corner| {
+                return *mesh.corners.entry(corner).or_insert_with(|| {
+                    let (p, t, n) = corner;
+                    mesh.positions.push(self.positions[p]);
+                    mesh.uvs.push(t.map(|t| self.uvs[t]));
+                    mesh.normals.push(n.map(|n| self.normals[n]));
+                    return mesh.positions.len() - 1;
+                });
+            })
+            .collect();
+        for k in 1..vertices.len() - 1 {
+            mesh.indices
+                .push([vertices[0], vertices[k], vertices[k + 1]]);
+        }
+        return Ok(());
+    }
+
+    /// Parses `v`, `v/vt`, `v//vn` or `v/vt/vn` into zero-based indices.
+    fn parse_corner(&self, corner: &str) -> Result<(usize, Option<usize>, Option<usize>), String> {
+        let mut parts = corner.split('/');
+        let position = resolve_index(parts.next(), self.positions.len(), "vertex")?
+            .ok_or_else(|| format!("face vertex '{}' has no position", corner))?;
+        let uv = resolve_index(parts.next(), self.uvs.len(), "texture coordinate")?;
+        let normal = resolve_index(parts.next(), self.normals.len(), "normal")?;
+        if parts.next().is_some() {
+            return Err(format!("malformed face vertex '{}'", corner));
+        }
+        return Ok((position, uv, normal));
+    }
+}
+
+impl PendingMesh {
+    /// Per-vertex attributes are only kept when every vertex of the mesh has them.
+    fn finish(self, material: Arc<dyn Material>) -> TriangleMesh {
+        let uvs = self.uvs.iter().copied().collect::<Option<Vec<_>>>();
+        let normals = self.normals.iter().copied().collect::<Option<Vec<_>>>();
+        return TriangleMesh {
+            positions: self.positions,
+            normals: normals.unwrap_or_default(),
+            uvs: uvs.unwrap_or_default(),
+            indices: self.indices,
+            material,
+        };
+    }
+}
+
+/// Converts a 1-based (or negative, relative to the end) OBJ index to a 0-based one.
+fn resolve_index(value: Option<&str>, count: usize, what: &str) -> Result<Option<usize>, String> {
+    let Some(value) = value.filter(|v| !v.is_empty()) else {
+        return Ok(None);
+    };
+    let index: i64 = value
+        .parse()
+        .map_err(|_| format!("invalid {} index '{}'", what, value))?;
+    let resolved = if index < 0 {
+        count as i64 + index
+    } else {
+        index - 1
+    };
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(format!("{} index {} out of range", what, index));
+    }
+    return Ok(Some(resolved as usize));
+}
+
+fn parse_number(value: Option<&str>) -> Result<f64, String> {
+    let Some(value) = value else {
+        return Err("missing number".to_string());
+    };
+    return match value.parse::<f64>() {
+        Ok(number) if number.is_finite() => Ok(number),
+        _ => Err(format!("invalid number '{}'", value)),
+    };
+}
+
+fn parse_vec3(args: &[&str]) -> Result<DVec3, String> {
+    if args.len() < 3 {
+        return Err(format!("expected 3 numbers, found {}", args.len()));
+    }
+    return Ok(DVec3::new(
+        parse_number(Some(args[0]))?,
+        parse_number(Some(args[1]))?,
+        parse_number(Some(args[2]))?,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::HitRecord;
+    use crate::random::Rng;
+    use crate::ray::Ray;
+
+    fn gray() -> Arc<dyn Material> {
+        return Arc::new(Lambertian::new(DVec3::splat(0.5)));
+    }
+
+    fn parse(source: &str) -> Result<ObjModel, ObjError> {
+        return parse_obj(source, Path::new("test.obj"), gray());
+    }
+
+    #[test]
+    fn triangulates_polygons_as_fans() {
+        let model =
+            parse("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0.5 1.5 0\nv 0 1 0\nf 1 2 3 4 5\n").unwrap();
+        assert_eq!(model.meshes.len(), 1);
+        let mesh = &model.meshes[0].mesh;
+        assert_eq!(mesh.positions.len(), 5);
+        assert_eq!(mesh.indices, vec![[0, 1, 2], [0, 2, 3], [0, 3, 4]]);
+        assert!(mesh.normals.is_empty());
+        assert!(mesh.uvs.is_empty());
+        assert!(mesh.validate().is_ok());
+    }
+
+    #[test]
+    fn resolves_attribute_indices_and_negative_indices() {
+        let model = parse(
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+             vt 0 0\nvt 1 0\nvt 0 1\n\
+             vn 0 0 1\n\
+             f -3/1/1 -2/2/1 -1/3/1\n\
+             f 1//1 2//1 3//1\n",
+        )
+        .unwrap();
+        let mesh = &model.meshes[0].mesh;
+        // The second face has no UVs, so the corners differ from the first face's.
+        assert_eq!(mesh.positions.len(), 6);
+        assert_eq!(mesh.normals.len(), 6);
+        // Not every vertex has a UV, so UVs are dropped for the whole mesh.
+        assert!(mesh.uvs.is_empty());
+        assert_eq!(mesh.indices, vec![[0, 1, 2], [3, 4, 5]]);
+    }
+
+    #[test]
+    fn splits_meshes_by_group_and_material() {
+        let model = parse(
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+             g a\nusemtl red\nf 1 2 3\n\
+             g b\nf 1 2 3\n\
+             g a\nf 3 2 1\n\
+             usemtl blue\nf 1 2 3\n",
+        )
+        .unwrap();
+        let groups: Vec<&str> = model.meshes.iter().map(|m| m.group.as_str()).collect();
+        assert_eq!(groups, vec!["a", "b", "a"]);
+        assert_eq!(model.meshes[0].mesh.indices.len(), 2);
+        assert_eq!(model.into_hittables().len(), 4);
+    }
+
+    #[test]
+    fn reports_errors_with_line_numbers() {
+        let err = parse("v 0 0 0\nv 1 0 0\n\nf 1 2 3\n").err().unwrap();
+        assert_eq!(err.line, 4);
+        assert!(err.message.contains("out of range"), "{}", err.message);
+
+        let err = parse("v 0 0 0\nv 1 0\n").err().unwrap();
+        assert_eq!(err.line, 2);
+        assert_eq!(err.to_string(), "test.obj:2: expected 3 numbers, found 2");
+
+        let err = parse("v 0 0 0\nf 1 1\n").err().unwrap();
+        assert!(err.message.contains("at least 3"));
+    }
+
+    /// What `material` does with a ray hitting the floor at 45 degrees: whether it
+    /// scatters, the attenuation, the scattered direction and the emitted light.
+    fn probe(material: &dyn Material, seed: u64) -> (bool, DVec3, DVec3, DVec3) {
+        let ray_in = Ray {
+            origin: DVec3::new(-1.0, 1.0, 0.0),
+            dir: DVec3::new(1.0, -1.0, 0.0),
+            time: 0.0,
+        };
+        let rec = HitRecord {
+            p: DVec3::ZERO,
+            normal: DVec3::Y,
+            t: 1.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: true,
+            material: None,
+        };
+        let mut attenuation = DVec3::ZERO;
+        let mut scattered = ray_in;
+        let scatters = material.scatter(
+            &ray_in,
+            &rec,
+            &mut Rng::new(seed),
+            &mut attenuation,
+            &mut scattered,
+        );
+        let emitted = material.emitted(&rec);
+        return (scatters, attenuation, scattered.dir.normalize(), emitted);
+    }
+
+    #[test]
+    fn maps_mtl_materials() {
+        let materials = parse_mtl(
+            "newmtl matte\nKd 0.1 0.2 0.3\n\
+             newmtl glass\nNi 1.33\nd 0.2\n\
+             newmtl mirror\nillum 3\nKs 0.9 0.8 0.7\nNs 1000000\n\
+             newmtl lamp\nKd 0.5 0.5 0.5\nKe 4 3 2\n",
+            Path::new("test.mtl"),
+        )
+        .unwrap();
+        assert_eq!(materials.len(), 4);
+
+        // Lambertian: diffuse albedo and a cosine-weighted density.
+        let (scatters, attenuation, dir, emitted) = probe(materials["matte"].as_ref(), 1);
+        assert!(scatters && dir.y > 0.0);
+        assert_eq!(attenuation, DVec3::new(0.1, 0.2, 0.3));
+        assert_eq!(emitted, DVec3::ZERO);
+
+        // Metal: tinted by Ks, a nearly sharp mirror for a huge Ns.
+        let (scatters, attenuation, dir, _) = probe(materials["mirror"].as_ref(), 1);
+        assert!(scatters);
+        assert_eq!(attenuation, DVec3::new(0.9, 0.8, 0.7));
+        assert!((dir - DVec3::new(1.0, 1.0, 0.0).normalize()).length() < 0.01);
+
+        // Dielectric: clear, refracting by Snell's law with Ni whenever it transmits.
+        let glass = materials["glass"].as_ref();
+        let mut refracted = 0;
+        for seed in 0..50 {
+            let (scatters, attenuation, dir, _) = probe(glass, seed);
+            assert!(scatters);
+            assert_eq!(attenuation, DVec3::ONE);
+            if dir.y < 0.0 {
+                refracted += 1;
+                assert!((dir.x - 0.5f64.sqrt() / 1.33).abs() < 1e-9);
+            }
+        }
+        assert!(refracted > 25);
+
+        // DiffuseLight: emits Ke and does not scatter.
+        let (scatters, _, _, emitted) = probe(materials["lamp"].as_ref(), 1);
+        assert!(!scatters);
+        assert_eq!(emitted, DVec3::new(4.0, 3.0, 2.0));
+
+        let err = parse_mtl("newmtl x\nKd 1 one 1\n", Path::new("test.mtl"))
+            .err()
+            .unwrap();
+        assert_eq!(err.line, 2);
+    }
+}
@@ -0,0 +1,30 @@
+use glam::DVec3;
+
+/// Right-handed orthonormal basis, used to work in a frame aligned with a normal or
+/// an axis.
+#[derive(Clone, Copy, Debug)]
+pub struct Onb {
+    pub u: DVec3,
+    pub v: DVec3,
+    pub w: DVec3,
+}
+
+impl Onb {
+    /// Basis whose `w` axis points along `w` (which does not need to be normalized).
+    pub fn from_w(w: DVec3) -> Onb {
+        let w = w.normalize();
+        let u = w.any_orthonormal_vector();
+        let v = w.cross(u);
+        return Onb { u, v, w };
+    }
+
+    /// Converts coordinates in this basis to world space.
+    pub fn to_world(&self, a: DVec3) -> DVec3 {
+        return a.x * self.u + a.y * self.v + a.z * self.w;
+    }
+
+    /// Converts a world space vector to coordinates in this basis.
+    pub fn to_local(&self, a: DVec3) -> DVec3 {
+        return DVec3::new(a.dot(self.u), a.dot(self.v), a.dot(self.w));
+    }
+}
@@ -0,0 +1,265 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use glam::DVec3;
+
+use crate::color::OutputTransform;
+use crate::image::Image;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    PpmAscii,
+    PpmBinary,
+    Png,
+    /// Radiance RGBE, keeps the unclamped linear radiance.
+    Hdr,
+}
+
+impl Format {
+    /// Picks the encoder from the file extension. `.ppm` files are written as
+    /// binary P6, ASCII P3 is only used when streaming to stdout.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        return match extension.as_str() {
+            "ppm" => Some(Format::PpmBinary),
+            "png" => Some(Format::Png),
+            "hdr" => Some(Format::Hdr),
+            _ => None,
+        };
+    }
+}
+
+/// Encodes `image` into the file at `path`, choosing the format from its extension.
+pub fn save(path: &Path, image: &Image, transform: &OutputTransform) -> io::Result<()> {
+    let Some(format) = Format::from_path(path) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unsupported output format '{}'", path.display()),
+        ));
+    };
+    let mut out = BufWriter::new(File::create(path)?);
+    return write(&mut out, format, image, transform);
+}
+
+/// Encodes `image` in the given `format`.
+pub fn write(
+    out: &mut impl Write,
+    format: Format,
+    image: &Image,
+    transform: &OutputTransform,
+) -> io::Result<()> {
+    return match format {
+        Format::PpmAscii => write_ppm_ascii(out, image, transform),
+        Format::PpmBinary => write_ppm_binary(out, image, transform),
+        Format::Png => write_png(out, image, transform),
+        Format::Hdr => write_hdr(out, image),
+    };
+}
+
+/// Writes `image` as an ASCII (P3) PPM.
+pub fn write_ppm_ascii(
+    out: &mut impl Write,
+    image: &Image,
+    transform: &OutputTransform,
+) -> io::Result<()> {
+    writeln!(out, "P3\n{} {}\n255", image.width, image.height)?;
+    for row in image.rows() {
+        for &pixel_color in row {
+            write_color(out, pixel_color, transform)?;
+        }
+    }
+    return out.flush();
+}
+
+/// Writes `image` as a binary (P6) PPM.
+pub fn write_ppm_binary(
+    out: &mut impl Write,
+    image: &Image,
+    transform: &OutputTransform,
+) -> io::Result<()> {
+    write!(out, "P6\n{} {}\n255\n", image.width, image.height)?;
+    out.write_all(&quantize_image(image, transform))?;
+    return out.flush();
+}
+
+/// Writes `image` as an 8-bit RGB PNG.
+pub fn write_png(
+    out: &mut impl Write,
+    image: &Image,
+    transform: &OutputTransform,
+) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(&mut *out, image.width as u32, image.height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&quantize_image(image, transform))?;
+    writer.finish()?;
+    return out.flush();
+}
+
+/// Writes the linear radiance of `image` as an uncompressed Radiance RGBE (.hdr) file.
+/// No output transform is applied so the file can be graded later.
+pub fn write_hdr(out: &mut impl Write, image: &Image) -> io::Result<()> {
+    write!(
+        out,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        image.height, image.width
+    )?;
+    for row in image.rows() {
+        for &pixel_color in row {
+            out.write_all(&to_rgbe(pixel_color))?;
+        }
+    }
+    return out.flush();
+}
+
+/// Shared-exponent encoding: three 8-bit mantissas scaled by the largest component.
+fn to_rgbe(color: DVec3) -> [u8; 4] {
+    // `max` also maps NaN to zero.
+    let color = color.max(DVec3::ZERO);
+    let brightest = color.max_element();
+    if brightest < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    // Clamped in floating point, where infinity cannot overflow, to the largest
+    // exponent a byte stores; brighter values saturate the mantissas instead.
+    let exponent = (brightest.log2().floor() + 1.0).min(127.0) as i32;
+    let scale = 256.0 / 2f64.powi(exponent);
+    return [
+        (color.x * scale).min(255.0) as u8,
+        (color.y * scale).min(255.0) as u8,
+        (color.z * scale).min(255.0) as u8,
+        (exponent + 128) as u8,
+    ];
+}
+
+fn write_color(
+    out: &mut impl Write,
+    pixel_color: DVec3,
+    transform: &OutputTransform,
+) -> io::Result<()> {
+    let [r, g, b] = quantize(pixel_color, transform);
+    return writeln!(out, "{} {} {}", r, g, b);
+}
+
+/// Applies the output transform and converts the result to 8-bit components.
+pub fn quantize(pixel_color: DVec3, transform: &OutputTransform) -> [u8; 3] {
+    let pixel_color = transform.apply(pixel_color);
+    return [
+        (255.999 * pixel_color.x).floor() as u8,
+        (255.999 * pixel_color.y).floor() as u8,
+        (255.999 * pixel_color.z).floor() as u8,
+    ];
+}
+
+fn quantize_image(image: &Image, transform: &OutputTransform) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(image.width * image.height * 3);
+    for row in image.rows() {
+        for &pixel_color in row {
+            bytes.extend_from_slice(&quantize(pixel_color, transform));
+        }
+    }
+    return bytes;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Radiance of an RGBE pixel, with each mantissa taken from the middle of its step.
+    fn from_rgbe([r, g, b, e]: [u8; 4]) -> DVec3 {
+        if e == 0 {
+            return DVec3::ZERO;
+        }
+        let scale = 2f64.powi(e as i32 - 128 - 8);
+        return (DVec3::new(r as f64, g as f64, b as f64) + 0.5) * scale;
+    }
+
+    #[test]
+    fn formats_are_chosen_by_extension() {
+        let format = |path: &str| Format::from_path(Path::new(path));
+        assert_eq!(format("out.ppm"), Some(Format::PpmBinary));
+        assert_eq!(format("renders/out.png"), Some(Format::Png));
+        assert_eq!(format("out.hdr"), Some(Format::Hdr));
+        assert_eq!(format("OUT.PNG"), Some(Format::Png));
+        assert_eq!(format("out.Ppm"), Some(Format::PpmBinary));
+        assert_eq!(format("out.jpg"), None);
+        assert_eq!(format("out.png.bak"), None);
+        assert_eq!(format("out"), None);
+    }
+
+    #[test]
+    fn ppm_headers_give_the_size_and_range() {
+        let mut image = Image::new(2, 1);
+        image.set(1, 0, DVec3::new(1.0, 0.5, 0.0));
+        let transform = OutputTransform::default();
+
+        let mut bytes = Vec::new();
+        write_ppm_ascii(&mut bytes, &image, &transform).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            "P3\n2 1\n255\n0 0 0\n255 188 0\n"
+        );
+
+        let mut bytes = Vec::new();
+        write_ppm_binary(&mut bytes, &image, &transform).unwrap();
+        let header = b"P6\n2 1\n255\n";
+        assert_eq!(&bytes[..header.len()], header);
+        assert_eq!(&bytes[header.len()..], [0, 0, 0, 255, 188, 0]);
+    }
+
+    #[test]
+    fn png_starts_with_its_signature() {
+        let mut bytes = Vec::new();
+        write_png(&mut bytes, &Image::new(3, 2), &OutputTransform::default()).unwrap();
+        assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+        // The IHDR chunk follows with the big-endian width and height.
+        assert_eq!(&bytes[12..24], b"IHDR\0\0\0\x03\0\0\0\x02");
+    }
+
+    #[test]
+    fn hdr_starts_with_a_radiance_header() {
+        let image = Image::new(3, 2);
+        let mut bytes = Vec::new();
+        write_hdr(&mut bytes, &image).unwrap();
+        let header = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 3\n";
+        assert_eq!(&bytes[..header.len()], header);
+        assert_eq!(bytes.len(), header.len() + 6 * 4);
+        assert!(bytes[header.len()..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn rgbe_round_trips_known_values() {
+        assert_eq!(to_rgbe(DVec3::ZERO), [0, 0, 0, 0]);
+        assert_eq!(to_rgbe(DVec3::ONE), [128, 128, 128, 129]);
+        assert_eq!(to_rgbe(DVec3::new(0.5, 0.25, 0.0)), [128, 64, 0, 128]);
+        for color in [
+            DVec3::new(0.2, 0.4, 0.8),
+            DVec3::new(1000.0, 3.0, 0.01),
+            DVec3::splat(1e-20),
+        ] {
+            let decoded = from_rgbe(to_rgbe(color));
+            // Each mantissa has 8 bits relative to the brightest component.
+            let step = color.max_element() / 128.0;
+            assert!((decoded - color).abs().max_element() <= step, "{}", color);
+        }
+    }
+
+    #[test]
+    fn rgbe_cleans_up_nan_negative_and_huge_values() {
+        assert_eq!(to_rgbe(DVec3::new(f64::NAN, -1.0, 0.5)), [0, 0, 128, 128]);
+        let largest = [255, 255, 255, 255];
+        assert_eq!(
+            to_rgbe(DVec3::splat(2f64.powi(127) * 0.99)),
+            [253, 253, 253, 255]
+        );
+        assert_eq!(to_rgbe(DVec3::splat(2f64.powi(127))), largest);
+        assert_eq!(to_rgbe(DVec3::splat(1e300)), largest);
+        assert_eq!(
+            to_rgbe(DVec3::new(f64::INFINITY, 0.0, 1e300)),
+            [255, 0, 255, 255]
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use glam::DVec3;
+
+use crate::random::{random_unit_vector, Rng};
+
+const POINT_COUNT: usize = 256;
+
+/// Gradient noise over 3D space with a period of 256 units along each axis.
+pub struct Perlin {
+    gradients: Vec<DVec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    /// Noise with its random gradients and permutations drawn from `rng`.
+    pub fn new(rng: &mut Rng) -> Perlin {
+        let gradients = (0..POINT_COUNT).map(|_| random_unit_vector(rng)).collect();
+        return Perlin {
+            gradients,
+            perm_x: generate_perm(rng),
+            perm_y: generate_perm(rng),
+            perm_z: generate_perm(rng),
+        };
+    }
+
+    /// Noise value at `p`, roughly in [-1, 1].
+    pub fn noise(&self, p: DVec3) -> f64 {
+        let floor = p.floor();
+        let frac = p - floor;
+        let (i, j, k) = (floor.x as i64, floor.y as i64, floor.z as i64);
+
+        let mut corners = [[[DVec3::ZERO; 2]; 2]; 2];
+        for (di, plane) in corners.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    let index = self.perm_x[wrap(i + di as i64)]
+                        ^ self.perm_y[wrap(j + dj as i64)]
+                        ^ self.perm_z[wrap(k + dk as i64)];
+                    *corner = self.gradients[index];
+                }
+            }
+        }
+        return interpolate(&corners, frac);
+    }
+
+    /// Sum of `depth` octaves of absolute noise, each at double the frequency and
+    /// half the weight of the previous one.
+    pub fn turbulence(&self, p: DVec3, depth: u32) -> f64 {
+        let mut accum = 0.0;
+        let mut p = p;
+        let mut weight = 1.0;
+        for _ in 0..depth {
+            accum += weight * self.noise(p);
+            weight *= 0.5;
+            p *= 2.0;
+        }
+        return accum.abs();
+    }
+}
+
+fn wrap(i: i64) -> usize {
+    return (i & (POINT_COUNT as i64 - 1)) as usize;
+}
+
+/// Random permutation of 0..POINT_COUNT (Fisher-Yates).
+fn generate_perm(rng: &mut Rng) -> Vec<usize> {
+    let mut perm: Vec<usize> = (0..POINT_COUNT).collect();
+    for i in (1..POINT_COUNT).rev() {
+        let target = (rng.next_f64() * (i + 1) as f64) as usize;
+        perm.swap(i, target);
+    }
+    return perm;
+}
+
+/// Trilinear blend of the corner gradients' contributions, smoothed with a Hermite
+/// cubic so the noise has no visible grid artifacts.
+fn interpolate(corners: &[[[DVec3; 2]; 2]; 2], frac: DVec3) -> f64 {
+    let smooth = frac * frac * (3.0 - 2.0 * frac);
+    let mut accum = 0.0;
+    for (i, plane) in corners.iter().enumerate() {
+        for (j, row) in plane.iter().enumerate() {
+            for (k, gradient) in row.iter().enumerate() {
+                let (fi, fj, fk) = (i as f64, j as f64, k as f64);
+                let weight = frac - DVec3::new(fi, fj, fk);
+                accum += (fi * smooth.x + (1.0 - fi) * (1.0 - smooth.x))
+                    * (fj * smooth.y + (1.0 - fj) * (1.0 - smooth.y))
+                    * (fk * smooth.z + (1.0 - fk) * (1.0 - smooth.z))
+                    * gradient.dot(weight);
+            }
+        }
+    }
+    return accum;
+}
@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use glam::DVec3;
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::ray::Ray;
+
+/// Infinite plane through `point`. (u, v) are world-space distances along two
+/// tangent directions, so textures repeat across it.
+pub struct Plane {
+    pub point: DVec3,
+    /// Front side of the plane, does not need to be normalized.
+    pub normal: DVec3,
+    pub material: Arc<dyn Material>,
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let normal = self.normal.normalize();
+        let denom = normal.dot(ray.dir);
+        if denom.abs() < 1e-12 {
+            return false;
+        }
+        let t = normal.dot(self.point - ray.origin) / denom;
+        if t < t_min || t_max < t {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = ray.at(t);
+        rec.set_face_normal(ray, normal);
+        let onb = Onb::from_w(normal);
+        let local = onb.to_local(rec.p - self.point);
+        rec.u = local.x;
+        rec.v = local.y;
+        rec.material = Some(self.material.clone());
+        return true;
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        return Aabb::new(DVec3::NEG_INFINITY, DVec3::INFINITY);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    fn record() -> HitRecord {
+        return HitRecord {
+            p: DVec3::ZERO,
+            normal: DVec3::ZERO,
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: false,
+            material: None,
+        };
+    }
+
+    fn floor() -> Plane {
+        return Plane {
+            point: DVec3::new(0.0, 1.0, 0.0),
+            normal: DVec3::new(0.0, 2.0, 0.0),
+            material: Arc::new(Lambertian::new(DVec3::splat(0.5))),
+        };
+    }
+
+    fn hit(origin: DVec3, dir: DVec3) -> Option<HitRecord> {
+        let ray = Ray {
+            origin,
+            dir,
+            time: 0.0,
+        };
+        let mut rec = record();
+        return floor()
+            .hit(&ray, 0.001, f64::INFINITY, &mut rec)
+            .then_some(rec);
+    }
+
+    #[test]
+    fn hits_face_the_incoming_ray() {
+        let rec = hit(DVec3::new(0.0, 5.0, 0.0), DVec3::NEG_Y).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-9);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, DVec3::Y);
+        assert_eq!((rec.u, rec.v), (0.0, 0.0));
+
+        let rec = hit(DVec3::new(2.0, -1.0, 0.0), DVec3::new(0.0, 4.0, 0.0)).unwrap();
+        assert!((rec.t - 0.5).abs() < 1e-9);
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, DVec3::NEG_Y);
+    }
+
+    #[test]
+    fn uvs_measure_distances_along_the_plane() {
+        let rec = hit(DVec3::new(3.0, 2.0, 4.0), DVec3::NEG_Y).unwrap();
+        assert!((rec.p - DVec3::new(3.0, 1.0, 4.0)).length() < 1e-9);
+        assert!((rec.u.hypot(rec.v) - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn parallel_and_receding_rays_miss() {
+        assert!(hit(DVec3::new(0.0, 2.0, 0.0), DVec3::X).is_none());
+        assert!(hit(DVec3::new(0.0, 1.0, 0.0), DVec3::new(1.0, 0.0, 1.0)).is_none());
+        assert!(hit(DVec3::new(0.0, 2.0, 0.0), DVec3::Y).is_none());
+    }
+
+    #[test]
+    fn bounding_box_is_unbounded() {
+        let bbox = floor().bounding_box();
+        assert_eq!(bbox.min, DVec3::NEG_INFINITY);
+        assert_eq!(bbox.max, DVec3::INFINITY);
+    }
+}
@@ -0,0 +1,174 @@
+use std::sync::Arc;
+
+use glam::DVec3;
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::random::Rng;
+use crate::ray::Ray;
+
+/// Parallelogram with corners `corner`, `corner + u`, `corner + v` and
+/// `corner + u + v`. The front side is the one `u × v` points to.
+#[derive(Clone)]
+pub struct Quad {
+    pub corner: DVec3,
+    pub u: DVec3,
+    pub v: DVec3,
+    pub material: Arc<dyn Material>,
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let n = self.u.cross(self.v);
+        let normal = n.normalize();
+        let denom = normal.dot(ray.dir);
+        if denom.abs() < 1e-12 {
+            return false;
+        }
+        let t = normal.dot(self.corner - ray.origin) / denom;
+        if t < t_min || t_max < t {
+            return false;
+        }
+
+        // Express the hit point in the (u, v) frame of the parallelogram.
+        let p = ray.at(t);
+        let planar = p - self.corner;
+        let w = n / n.dot(n);
+        let alpha = w.dot(planar.cross(self.v));
+        let beta = w.dot(self.u.cross(planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = alpha;
+        rec.v = beta;
+        rec.set_face_normal(ray, normal);
+        rec.material = Some(self.material.clone());
+        return true;
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let a = Aabb::new(self.corner, self.corner + self.u + self.v);
+        let b = Aabb::new(self.corner + self.u, self.corner + self.v);
+        return Aabb::surrounding(&a, &b);
+    }
+
+    /// Uniform sampling over the area, converted to solid angle at `origin`.
+    fn pdf_value(&self, origin: DVec3, direction: DVec3) -> f64 {
+        let mut rec = HitRecord {
+            p: DVec3::ZERO,
+            normal: DVec3::ZERO,
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: false,
+            material: None,
+        };
+        let ray = Ray {
+            origin,
+            dir: direction,
+            time: 0.0,
+        };
+        if !self.hit(&ray, 0.001, f64::INFINITY, &mut rec) {
+            return 0.0;
+        }
+        let n = self.u.cross(self.v);
+        let area = n.length();
+        let distance_squared = rec.t * rec.t * direction.length_squared();
+        let cosine = (direction.dot(n) / (direction.length() * area)).abs();
+        if cosine < 1e-12 {
+            return 0.0;
+        }
+        return distance_squared / (cosine * area);
+    }
+
+    fn random(&self, origin: DVec3, rng: &mut Rng) -> DVec3 {
+        let p = self.corner + rng.next_f64() * self.u + rng.next_f64() * self.v;
+        return p - origin;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::random::random_unit_vector;
+
+    fn record() -> HitRecord {
+        return HitRecord {
+            p: DVec3::ZERO,
+            normal: DVec3::ZERO,
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: false,
+            material: None,
+        };
+    }
+
+    fn quad() -> Quad {
+        return Quad {
+            corner: DVec3::new(-1.0, -1.0, -2.0),
+            u: DVec3::new(2.0, 0.0, 0.0),
+            v: DVec3::new(0.0, 4.0, 0.0),
+            material: Arc::new(Lambertian::new(DVec3::splat(0.5))),
+        };
+    }
+
+    #[test]
+    fn hit_reports_parallelogram_coordinates() {
+        let ray = Ray {
+            origin: DVec3::new(0.5, 0.0, 0.0),
+            dir: DVec3::NEG_Z,
+            time: 0.0,
+        };
+        let mut rec = record();
+        assert!(quad().hit(&ray, 0.0, f64::INFINITY, &mut rec));
+        assert!((rec.t - 2.0).abs() < 1e-9);
+        assert!((rec.u - 0.75).abs() < 1e-9);
+        assert!((rec.v - 0.25).abs() < 1e-9);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, DVec3::Z);
+    }
+
+    #[test]
+    fn misses_outside_and_parallel() {
+        let mut rec = record();
+        let outside = Ray {
+            origin: DVec3::new(1.5, 0.0, 0.0),
+            dir: DVec3::NEG_Z,
+            time: 0.0,
+        };
+        assert!(!quad().hit(&outside, 0.0, f64::INFINITY, &mut rec));
+        let parallel = Ray {
+            origin: DVec3::new(0.0, 0.0, -2.0),
+            dir: DVec3::X,
+            time: 0.0,
+        };
+        assert!(!quad().hit(&parallel, 0.0, f64::INFINITY, &mut rec));
+    }
+
+    #[test]
+    fn light_sampling_density_integrates_to_one() {
+        let q = quad();
+        // Straight at the plane: squared distance 4 over cosine 1 times area 8.
+        assert!((q.pdf_value(DVec3::ZERO, DVec3::new(0.0, 0.0, -2.0)) - 0.5).abs() < 1e-9);
+        assert_eq!(q.pdf_value(DVec3::ZERO, DVec3::Z), 0.0);
+
+        let mut rng = Rng::new(3);
+        for _ in 0..100 {
+            let dir = q.random(DVec3::ZERO, &mut rng);
+            assert!(q.pdf_value(DVec3::ZERO, dir) > 0.0);
+        }
+        // Estimate the integral of the density over the sphere of directions.
+        let count = 200_000;
+        let sum: f64 = (0..count)
+            .map(|_| q.pdf_value(DVec3::ZERO, random_unit_vector(&mut rng)))
+            .sum();
+        let integral = 4.0 * std::f64::consts::PI * sum / count as f64;
+        assert!((integral - 1.0).abs() < 0.02, "{}", integral);
+    }
+}
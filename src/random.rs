@@ -0,0 +1,61 @@
+use glam::DVec3;
+
+/// Small deterministic generator (SplitMix64) so renders are reproducible from a seed
+/// regardless of platform or dependency versions.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        return Rng { state: seed };
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        return finalize(self.state);
+    }
+
+    /// Uniform value in [0, 1).
+    pub fn next_f64(&mut self) -> f64 {
+        return (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64);
+    }
+}
+
+/// Combines `seed` with `value` into a new well-mixed seed, e.g. to derive one
+/// independent stream per pixel and per sample.
+pub fn mix(seed: u64, value: u64) -> u64 {
+    return finalize(seed ^ finalize(value.wrapping_add(0x9e37_79b9_7f4a_7c15)));
+}
+
+fn finalize(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    return z ^ (z >> 31);
+}
+
+/// Uniformly distributed point inside the unit sphere.
+pub fn random_in_unit_sphere(rng: &mut Rng) -> DVec3 {
+    loop {
+        let p = DVec3::new(
+            2.0 * rng.next_f64() - 1.0,
+            2.0 * rng.next_f64() - 1.0,
+            2.0 * rng.next_f64() - 1.0,
+        );
+        if p.length_squared() < 1.0 {
+            return p;
+        }
+    }
+}
+
+/// Uniformly distributed direction.
+pub fn random_unit_vector(rng: &mut Rng) -> DVec3 {
+    loop {
+        let p = random_in_unit_sphere(rng);
+        let len_sq = p.length_squared();
+        // Reject points too close to the center, their normalization is unstable.
+        if len_sq > 1e-160 {
+            return p / len_sq.sqrt();
+        }
+    }
+}
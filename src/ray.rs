@@ -0,0 +1,18 @@
+use glam::DVec3;
+
+/// Half-line `origin + t * dir`. The direction does not have to be normalized.
+#[derive(Clone, Copy, Debug)]
+pub struct Ray {
+    pub origin: DVec3,
+    pub dir: DVec3,
+    /// Moment the ray exists at, which decides where moving objects are. The camera
+    /// shutter covers part of [0, 1] and scattered rays inherit the time.
+    pub time: f64,
+}
+
+impl Ray {
+    /// Point at parameter `t` along the ray.
+    pub fn at(&self, t: f64) -> DVec3 {
+        return self.origin + t * self.dir;
+    }
+}
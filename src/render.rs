@@ -0,0 +1,555 @@
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use glam::{DVec2, DVec3};
+
+use crate::camera::Camera;
+use crate::hittable::{HitRecord, Hittable, HittableList};
+use crate::image::Image;
+use crate::material::Material;
+use crate::random::{mix, Rng};
+use crate::ray::Ray;
+use crate::sampler::SamplePattern;
+
+const TILE_SIZE: usize = 16;
+
+/// Parameters of [`render`] that do not belong to the scene's geometry.
+pub struct RenderSettings {
+    pub samples_per_pixel: u32,
+    pub max_depth: u32,
+    pub seed: u64,
+    pub threads: usize,
+    pub background: Background,
+    /// How light samples and BSDF samples are weighted against each other.
+    pub mis: MisHeuristic,
+}
+
+/// Weighting of the two ways to reach a light in multiple importance sampling.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MisHeuristic {
+    /// Weights proportional to the densities.
+    Balance,
+    /// Weights proportional to the squared densities, which is usually less noisy.
+    Power,
+}
+
+impl MisHeuristic {
+    /// Weight of a sample drawn with density `pdf` when it could also have been
+    /// drawn by the other strategy with density `other_pdf`.
+    fn weight(self, pdf: f64, other_pdf: f64) -> f64 {
+        return match self {
+            MisHeuristic::Balance => pdf / (pdf + other_pdf),
+            MisHeuristic::Power => pdf * pdf / (pdf * pdf + other_pdf * other_pdf),
+        };
+    }
+}
+
+impl FromStr for MisHeuristic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s.to_ascii_lowercase().as_str() {
+            "balance" => Ok(MisHeuristic::Balance),
+            "power" => Ok(MisHeuristic::Power),
+            _ => Err(format!(
+                "unknown MIS heuristic '{}' (expected balance or power)",
+                s
+            )),
+        };
+    }
+}
+
+/// Radiance arriving along rays that leave the scene without hitting anything.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Background {
+    /// Vertical blend from `bottom` (looking straight down) to `top` (straight up).
+    Gradient { bottom: DVec3, top: DVec3 },
+    /// The same color in every direction; black leaves emitters as the only light.
+    Solid(DVec3),
+}
+
+impl Default for Background {
+    /// Light blue sky.
+    fn default() -> Self {
+        return Background::Gradient {
+            bottom: DVec3::new(1.0, 1.0, 1.0),
+            top: DVec3::new(0.5, 0.7, 1.0),
+        };
+    }
+}
+
+impl Background {
+    pub fn color(&self, dir: DVec3) -> DVec3 {
+        return match *self {
+            Background::Gradient { bottom, top } => {
+                let a = 0.5 * (dir.normalize().y + 1.0);
+                (1.0 - a) * bottom + a * top
+            }
+            Background::Solid(color) => color,
+        };
+    }
+}
+
+struct Tile {
+    x0: usize,
+    y0: usize,
+    width: usize,
+    height: usize,
+}
+
+/// When a [`render_progressive`] run stops and how often it reports the image.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Progressive {
+    /// Stop after the pass during which this much time has passed, even if fewer
+    /// than `samples_per_pixel` passes are done.
+    pub time_limit: Option<Duration>,
+    /// Report the image after every this many passes.
+    pub checkpoint_passes: Option<u32>,
+    /// Report the image after the first pass that ends this long after the last report.
+    pub checkpoint_interval: Option<Duration>,
+}
+
+/// Renders the full image, splitting it into tiles that are handed out to
+/// `settings.threads` workers. Every pixel draws its random numbers from a seed
+/// derived from its coordinates, so the result does not depend on the thread count.
+///
+/// `lights` holds copies of the emissive objects in `world` that shadow rays are
+/// aimed at; with an empty list, lights are only found by following BSDF samples.
+pub fn render(
+    camera: &Camera,
+    world: &dyn Hittable,
+    lights: &HittableList,
+    pattern: &dyn SamplePattern,
+    settings: &RenderSettings,
+) -> Image {
+    let mut image = Image::new(camera.image_width as usize, camera.image_height as usize);
+    let tiles = tiles(&image);
+    let sums = render_tiles(&tiles, settings.threads, true, |tile| {
+        return render_tile(
+            camera,
+            world,
+            lights,
+            pattern,
+            settings,
+            tile,
+            0..settings.samples_per_pixel,
+        );
+    });
+    for (index, pixels) in sums {
+        let tile = &tiles[index];
+        for (n, sum) in pixels.into_iter().enumerate() {
+            image.set(
+                tile.x0 + n % tile.width,
+                tile.y0 + n / tile.width,
+                sum / settings.samples_per_pixel as f64,
+            );
+        }
+    }
+    return image;
+}
+
+/// Renders the image in passes of one sample per pixel, averaging all passes so far.
+/// `checkpoint` receives that average and the number of passes whenever
+/// `progressive` asks for it. Stops after `settings.samples_per_pixel` passes or
+/// when the time limit is reached, and returns the final average.
+///
+/// A run that completes all passes produces exactly the image of [`render`].
+pub fn render_progressive(
+    camera: &Camera,
+    world: &dyn Hittable,
+    lights: &HittableList,
+    pattern: &dyn SamplePattern,
+    settings: &RenderSettings,
+    progressive: &Progressive,
+    checkpoint: &mut dyn FnMut(&Image, u32),
+) -> Image {
+    let mut image = Image::new(camera.image_width as usize, camera.image_height as usize);
+    let tiles = tiles(&image);
+    let mut sums = vec![DVec3::ZERO; image.width * image.height];
+    let start = Instant::now();
+    let mut last_checkpoint = start;
+    let mut passes = 0;
+    while passes < settings.samples_per_pixel {
+        let pass = render_tiles(&tiles, settings.threads, false, |tile| {
+            return render_tile(
+                camera,
+                world,
+                lights,
+                pattern,
+                settings,
+                tile,
+                passes..passes + 1,
+            );
+        });
+        for (index, pixels) in pass {
+            let tile = &tiles[index];
+            for (n, color) in pixels.into_iter().enumerate() {
+                let (i, j) = (tile.x0 + n % tile.width, tile.y0 + n / tile.width);
+                sums[j * image.width + i] += color;
+            }
+        }
+        passes += 1;
+        eprintln!("\rPasses done: {}/{}", passes, settings.samples_per_pixel);
+
+        let now = Instant::now();
+        let finished = passes == settings.samples_per_pixel
+            || progressive
+                .time_limit
+                .is_some_and(|limit| now - start >= limit);
+        if finished {
+            break;
+        }
+        let due = progressive
+            .checkpoint_passes
+            .is_some_and(|every| passes % every == 0)
+            || progressive
+                .checkpoint_interval
+                .is_some_and(|interval| now - last_checkpoint >= interval);
+        if due {
+            average(&mut image, &sums, passes);
+            checkpoint(&image, passes);
+            last_checkpoint = Instant::now();
+        }
+    }
+    average(&mut image, &sums, passes);
+    return image;
+}
+
+/// Stores the per-pixel `sums` of `passes` samples divided by their count.
+fn average(image: &mut Image, sums: &[DVec3], passes: u32) {
+    for (index, sum) in sums.iter().enumerate() {
+        image.set(
+            index % image.width,
+            index / image.width,
+            *sum / passes as f64,
+        );
+    }
+}
+
+/// Splits the image into tiles in scanline order.
+fn tiles(image: &Image) -> Vec<Tile> {
+    let mut tiles = Vec::new();
+    for y0 in (0..image.height).step_by(TILE_SIZE) {
+        for x0 in (0..image.width).step_by(TILE_SIZE) {
+            tiles.push(Tile {
+                x0,
+                y0,
+                width: TILE_SIZE.min(image.width - x0),
+                height: TILE_SIZE.min(image.height - y0),
+            });
+        }
+    }
+    return tiles;
+}
+
+/// Runs `render_tile` on every tile using `threads` workers and returns each
+/// tile's pixels with the tile's index.
+fn render_tiles(
+    tiles: &[Tile],
+    threads: usize,
+    report: bool,
+    render_tile: impl Fn(&Tile) -> Vec<DVec3> + Sync,
+) -> Vec<(usize, Vec<DVec3>)> {
+    let next_tile = AtomicUsize::new(0);
+    return thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.max(1))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next_tile.fetch_add(1, Ordering::Relaxed);
+                        let Some(tile) = tiles.get(index) else {
+                            break;
+                        };
+                        if report {
+                            eprintln!("\rTiles remaining: {}", tiles.len() - index);
+                        }
+                        done.push((index, render_tile(tile)));
+                    }
+                    return done;
+                })
+            })
+            .collect();
+        return workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("render worker panicked"))
+            .collect();
+    });
+}
+
+/// Sum of the given samples for every pixel of `tile`, in scanline order.
+fn render_tile(
+    camera: &Camera,
+    world: &dyn Hittable,
+    lights: &HittableList,
+    pattern: &dyn SamplePattern,
+    settings: &RenderSettings,
+    tile: &Tile,
+    samples: Range<u32>,
+) -> Vec<DVec3> {
+    let scene = SceneRef {
+        world,
+        lights,
+        background: &settings.background,
+        mis: settings.mis,
+    };
+    let mut pixels = Vec::with_capacity(tile.width * tile.height);
+    for j in tile.y0..tile.y0 + tile.height {
+        for i in tile.x0..tile.x0 + tile.width {
+            pixels.push(render_pixel(
+                camera,
+                &scene,
+                pattern,
+                settings,
+                i as i32,
+                j as i32,
+                samples.clone(),
+            ));
+        }
+    }
+    return pixels;
+}
+
+/// Sum of the given samples of pixel (i, j), out of `settings.samples_per_pixel`.
+fn render_pixel(
+    camera: &Camera,
+    scene: &SceneRef,
+    pattern: &dyn SamplePattern,
+    settings: &RenderSettings,
+    i: i32,
+    j: i32,
+    samples: Range<u32>,
+) -> DVec3 {
+    let samples_per_pixel = settings.samples_per_pixel;
+    let pixel_seed = mix(mix(settings.seed, j as u64), i as u64);
+    let mut pixel_color = DVec3::new(0.0, 0.0, 0.0);
+    for sample in samples {
+        let offset = pattern.sample(sample, samples_per_pixel, pixel_seed);
+        // Seeded apart from the sample pattern's own streams.
+        let mut rng = Rng::new(mix(mix(pixel_seed, sample as u64), 1));
+        let lens = if camera.has_defocus() {
+            let mut lens_rng = Rng::new(mix(mix(pixel_seed, sample as u64), 2));
+            DVec2::new(lens_rng.next_f64(), lens_rng.next_f64())
+        } else {
+            DVec2::ZERO
+        };
+        let shutter = Rng::new(mix(mix(pixel_seed, sample as u64), 3)).next_f64();
+        let ray = camera.get_ray(i, j, offset, lens, shutter);
+        pixel_color += ray_color(ray, scene, settings.max_depth, &mut rng, None);
+    }
+    return pixel_color;
+}
+
+/// What a path needs to know about the scene.
+struct SceneRef<'a> {
+    world: &'a dyn Hittable,
+    lights: &'a HittableList,
+    background: &'a Background,
+    mis: MisHeuristic,
+}
+
+/// Radiance arriving along `ray`. `bsdf_pdf` is the density with which the previous
+/// bounce picked `ray`, or `None` for camera rays and specular bounces, whose light
+/// hits have no competing light sample and are therefore counted in full.
+fn ray_color(
+    ray: Ray,
+    scene: &SceneRef,
+    depth: u32,
+    rng: &mut Rng,
+    bsdf_pdf: Option<f64>,
+) -> DVec3 {
+    if depth == 0 {
+        return DVec3::new(0.0, 0.0, 0.0);
+    }
+
+    let mut rec = HitRecord {
+        p: DVec3::new(0.0, 0.0, 0.0),
+        normal: DVec3::new(0.0, 0.0, 0.0),
+        t: 0.0,
+        u: 0.0,
+        v: 0.0,
+        front_face: false,
+        material: None,
+    };
+    // Start slightly above zero so rays do not re-hit the surface they leave from.
+    if !scene.world.hit(&ray, 0.001, f64::INFINITY, &mut rec) {
+        return scene.background.color(ray.dir);
+    }
+    let Some(material) = rec.material.clone() else {
+        return DVec3::new(0.0, 0.0, 0.0);
+    };
+    let mut emitted = material.emitted(&rec);
+    if let Some(bsdf_pdf) = bsdf_pdf {
+        let light_pdf = scene.lights.pdf_value(ray.origin, ray.dir);
+        emitted *= scene.mis.weight(bsdf_pdf, light_pdf);
+    }
+
+    let mut attenuation = DVec3::new(0.0, 0.0, 0.0);
+    let mut scattered = Ray {
+        origin: rec.p,
+        dir: rec.normal,
+        time: ray.time,
+    };
+    if !material.scatter(&ray, &rec, rng, &mut attenuation, &mut scattered) {
+        return emitted;
+    }
+    let pdf = material.scattering_pdf(&ray, &rec, &scattered);
+    if pdf <= 0.0 || scene.lights.objects.is_empty() {
+        return emitted + attenuation * ray_color(scattered, scene, depth - 1, rng, None);
+    }
+    let direct = sample_light(&ray, &rec, material.as_ref(), attenuation, scene, rng);
+    return emitted + direct + attenuation * ray_color(scattered, scene, depth - 1, rng, Some(pdf));
+}
+
+/// Next-event estimation: light arriving at `rec` directly from a point picked on
+/// one of the lights, weighted against finding the same light by BSDF sampling.
+fn sample_light(
+    ray_in: &Ray,
+    rec: &HitRecord,
+    material: &dyn Material,
+    attenuation: DVec3,
+    scene: &SceneRef,
+    rng: &mut Rng,
+) -> DVec3 {
+    let shadow_ray = Ray {
+        origin: rec.p,
+        dir: scene.lights.random(rec.p, rng),
+        time: ray_in.time,
+    };
+    let light_pdf = scene.lights.pdf_value(shadow_ray.origin, shadow_ray.dir);
+    let bsdf_pdf = material.scattering_pdf(ray_in, rec, &shadow_ray);
+    if light_pdf <= 0.0 || bsdf_pdf <= 0.0 {
+        return DVec3::new(0.0, 0.0, 0.0);
+    }
+
+    let mut light_rec = HitRecord {
+        p: DVec3::new(0.0, 0.0, 0.0),
+        normal: DVec3::new(0.0, 0.0, 0.0),
+        t: 0.0,
+        u: 0.0,
+        v: 0.0,
+        front_face: false,
+        material: None,
+    };
+    if !scene
+        .world
+        .hit(&shadow_ray, 0.001, f64::INFINITY, &mut light_rec)
+    {
+        return DVec3::new(0.0, 0.0, 0.0);
+    }
+    let Some(light_material) = light_rec.material.as_ref() else {
+        return DVec3::new(0.0, 0.0, 0.0);
+    };
+    let emitted = light_material.emitted(&light_rec);
+    let weight = scene.mis.weight(light_pdf, bsdf_pdf);
+    // attenuation * bsdf_pdf is the BSDF times the cosine term.
+    return attenuation * bsdf_pdf * emitted * weight / light_pdf;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::CameraSettings;
+    use crate::material::{DiffuseLight, Lambertian};
+    use crate::sampler::Stratified;
+    use crate::sphere::Sphere;
+    use crate::texture::SolidColor;
+    use std::sync::Arc;
+
+    /// A lit sphere on a large ground sphere, spanning several tiles.
+    fn scene() -> (Camera, HittableList, HittableList) {
+        let camera = Camera::new(CameraSettings {
+            image_width: 40,
+            aspect_ratio: 2.0,
+            ..CameraSettings::default()
+        });
+        let lamp = Sphere {
+            center: DVec3::new(1.0, 1.0, -1.0),
+            radius: 0.3,
+            material: Arc::new(DiffuseLight {
+                emit: Arc::new(SolidColor {
+                    color: DVec3::splat(4.0),
+                }),
+                two_sided: false,
+            }),
+        };
+        let world = HittableList {
+            objects: vec![
+                Box::new(Sphere {
+                    center: DVec3::new(0.0, 0.0, -1.0),
+                    radius: 0.5,
+                    material: Arc::new(Lambertian::new(DVec3::splat(0.5))),
+                }),
+                Box::new(Sphere {
+                    center: DVec3::new(0.0, -100.5, -1.0),
+                    radius: 100.0,
+                    material: Arc::new(Lambertian::new(DVec3::splat(0.8))),
+                }),
+                Box::new(lamp.clone()),
+            ],
+        };
+        let lights = HittableList {
+            objects: vec![Box::new(lamp)],
+        };
+        return (camera, world, lights);
+    }
+
+    fn settings(threads: usize) -> RenderSettings {
+        return RenderSettings {
+            samples_per_pixel: 9,
+            max_depth: 5,
+            seed: 3,
+            threads,
+            background: Background::default(),
+            mis: MisHeuristic::Power,
+        };
+    }
+
+    fn assert_identical(image: &Image, expected: &Image) {
+        assert_eq!(
+            (image.width, image.height),
+            (expected.width, expected.height)
+        );
+        for j in 0..image.height {
+            for i in 0..image.width {
+                assert_eq!(image.get(i, j), expected.get(i, j), "pixel {}, {}", i, j);
+            }
+        }
+    }
+
+    #[test]
+    fn output_does_not_depend_on_thread_count() {
+        let (camera, world, lights) = scene();
+        let expected = render(&camera, &world, &lights, &Stratified, &settings(1));
+        for threads in [2, 5] {
+            let image = render(&camera, &world, &lights, &Stratified, &settings(threads));
+            assert_identical(&image, &expected);
+        }
+    }
+
+    #[test]
+    fn completed_progressive_render_matches_render() {
+        let (camera, world, lights) = scene();
+        let settings = settings(3);
+        let progressive = Progressive {
+            checkpoint_passes: Some(4),
+            ..Progressive::default()
+        };
+        let mut checkpoints = Vec::new();
+        let image = render_progressive(
+            &camera,
+            &world,
+            &lights,
+            &Stratified,
+            &settings,
+            &progressive,
+            &mut |_, passes| checkpoints.push(passes),
+        );
+        assert_eq!(checkpoints, vec![4, 8]);
+        let expected = render(&camera, &world, &lights, &Stratified, &settings);
+        assert_identical(&image, &expected);
+    }
+}
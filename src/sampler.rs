@@ -0,0 +1,240 @@
+use std::str::FromStr;
+
+use glam::DVec2;
+
+use crate::random::{mix, Rng};
+
+/// Distributes the samples of a pixel over the unit pixel square.
+pub trait SamplePattern: Send + Sync {
+    /// Position of sample `index` out of `count` in [0, 1)^2. The result must only
+    /// depend on the arguments so that renders are reproducible.
+    fn sample(&self, index: u32, count: u32, pixel_seed: u64) -> DVec2;
+}
+
+/// Uniformly random position inside the pixel.
+pub struct Jittered;
+
+/// One random position per cell of a grid covering the pixel, with one cell per
+/// sample and the grid as close to square as the sample count allows. Each pixel
+/// visits the cells in its own shuffled order, so that the first samples of a
+/// progressive render stopped early still cover the whole pixel.
+pub struct Stratified;
+
+/// Halton sequence in bases 2 and 3 with a per-pixel random shift.
+pub struct Halton;
+
+/// First two dimensions of the Sobol sequence with a per-pixel random shift.
+pub struct Sobol;
+
+impl SamplePattern for Jittered {
+    fn sample(&self, index: u32, _count: u32, pixel_seed: u64) -> DVec2 {
+        let mut rng = Rng::new(mix(pixel_seed, index as u64));
+        return DVec2::new(rng.next_f64(), rng.next_f64());
+    }
+}
+
+impl SamplePattern for Stratified {
+    fn sample(&self, index: u32, count: u32, pixel_seed: u64) -> DVec2 {
+        let (columns, rows) = grid(count.max(1));
+        let cell = permute(index % (columns * rows), columns * rows, pixel_seed as u32);
+        let mut rng = Rng::new(mix(pixel_seed, index as u64));
+        let x = (cell % columns) as f64 + rng.next_f64();
+        let y = (cell / columns) as f64 + rng.next_f64();
+        return DVec2::new(x / columns as f64, y / rows as f64);
+    }
+}
+
+impl SamplePattern for Halton {
+    fn sample(&self, index: u32, _count: u32, pixel_seed: u64) -> DVec2 {
+        let point = DVec2::new(radical_inverse(index, 2), radical_inverse(index, 3));
+        return shift(point, pixel_seed);
+    }
+}
+
+impl SamplePattern for Sobol {
+    fn sample(&self, index: u32, _count: u32, pixel_seed: u64) -> DVec2 {
+        let x = index.reverse_bits() as f64 / 2f64.powi(32);
+        let y = sobol_second_dimension(index) as f64 / 2f64.powi(32);
+        return shift(DVec2::new(x, y), pixel_seed);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatternKind {
+    Jittered,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+impl PatternKind {
+    pub fn pattern(self) -> Box<dyn SamplePattern> {
+        return match self {
+            PatternKind::Jittered => Box::new(Jittered),
+            PatternKind::Stratified => Box::new(Stratified),
+            PatternKind::Halton => Box::new(Halton),
+            PatternKind::Sobol => Box::new(Sobol),
+        };
+    }
+}
+
+impl FromStr for PatternKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s.to_ascii_lowercase().as_str() {
+            "jittered" => Ok(PatternKind::Jittered),
+            "stratified" => Ok(PatternKind::Stratified),
+            "halton" => Ok(PatternKind::Halton),
+            "sobol" => Ok(PatternKind::Sobol),
+            _ => Err(format!(
+                "unknown sample pattern '{}' (expected jittered, stratified, halton or sobol)",
+                s
+            )),
+        };
+    }
+}
+
+/// Columns and rows of the grid with exactly `count` cells that is closest to square.
+fn grid(count: u32) -> (u32, u32) {
+    let mut rows = (count as f64).sqrt() as u32;
+    while !count.is_multiple_of(rows) {
+        rows -= 1;
+    }
+    return (count / rows, rows);
+}
+
+/// Element `index` of a random permutation of 0..len chosen by `seed`, computed
+/// without storing the permutation (Kensler, "Correlated Multi-Jittered Sampling").
+fn permute(mut index: u32, len: u32, seed: u32) -> u32 {
+    let mut mask = len - 1;
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    mask |= mask >> 8;
+    mask |= mask >> 16;
+    // Hash within the next power of two, walking the cycle until the result fits.
+    loop {
+        index ^= seed;
+        index = index.wrapping_mul(0xe170893d);
+        index ^= seed >> 16;
+        index ^= (index & mask) >> 4;
+        index ^= seed >> 8;
+        index = index.wrapping_mul(0x0929eb3f);
+        index ^= seed >> 23;
+        index ^= (index & mask) >> 1;
+        index = index.wrapping_mul(1 | seed >> 27);
+        index = index.wrapping_mul(0x6935fa69);
+        index ^= (index & mask) >> 11;
+        index = index.wrapping_mul(0x74dcb303);
+        index ^= (index & mask) >> 2;
+        index = index.wrapping_mul(0x9e501cc3);
+        index ^= (index & mask) >> 2;
+        index = index.wrapping_mul(0xc860a3df);
+        index &= mask;
+        index ^= index >> 5;
+        if index < len {
+            return ((index as u64 + seed as u64) % len as u64) as u32;
+        }
+    }
+}
+
+/// Cranley-Patterson rotation: decorrelates neighbouring pixels that would otherwise
+/// all use the exact same low-discrepancy points.
+fn shift(point: DVec2, pixel_seed: u64) -> DVec2 {
+    let mut rng = Rng::new(pixel_seed);
+    let offset = DVec2::new(rng.next_f64(), rng.next_f64());
+    return (point + offset).fract();
+}
+
+fn radical_inverse(mut index: u32, base: u32) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut factor = inv_base;
+    let mut result = 0.0;
+    while index > 0 {
+        result += (index % base) as f64 * factor;
+        index /= base;
+        factor *= inv_base;
+    }
+    return result;
+}
+
+fn sobol_second_dimension(mut index: u32) -> u32 {
+    let mut v = 1u32 << 31;
+    let mut result = 0;
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= v;
+        }
+        index >>= 1;
+        v ^= v >> 1;
+    }
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grids_use_every_cell() {
+        assert_eq!(grid(1), (1, 1));
+        assert_eq!(grid(5), (5, 1));
+        assert_eq!(grid(12), (4, 3));
+        assert_eq!(grid(16), (4, 4));
+    }
+
+    #[test]
+    fn permutations_reach_every_index_once() {
+        for len in [1, 2, 5, 16, 33] {
+            for seed in [0, 7, 0xdeadbeef] {
+                let mut seen: Vec<u32> = (0..len).map(|i| permute(i, len, seed)).collect();
+                seen.sort();
+                assert_eq!(seen, (0..len).collect::<Vec<_>>(), "{} {}", len, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn partial_stratified_runs_are_centered() {
+        // A progressive render stopped after `taken` of `count` passes.
+        for (count, taken) in [(16, 1), (16, 5), (16, 7), (6, 4), (64, 10)] {
+            let pixels = 4000;
+            let mut mean = DVec2::ZERO;
+            for pixel in 0..pixels {
+                let pixel_seed = mix(pixel, 3);
+                for index in 0..taken {
+                    mean += Stratified.sample(index, count, pixel_seed);
+                }
+            }
+            mean /= (pixels * taken as u64) as f64;
+            assert!(
+                (mean - DVec2::splat(0.5)).abs().max_element() < 0.01,
+                "{} of {}: {}",
+                taken,
+                count,
+                mean
+            );
+        }
+    }
+
+    #[test]
+    fn stratified_samples_are_centered_for_any_count() {
+        for count in [2, 3, 5, 6, 16] {
+            let pixels = 4000;
+            let mut mean = DVec2::ZERO;
+            for pixel in 0..pixels {
+                for index in 0..count {
+                    mean += Stratified.sample(index, count, pixel);
+                }
+            }
+            mean /= (pixels * count as u64) as f64;
+            assert!(
+                (mean - DVec2::splat(0.5)).abs().max_element() < 0.01,
+                "{}: {}",
+                count,
+                mean
+            );
+        }
+    }
+}
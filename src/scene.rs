@@ -0,0 +1,1191 @@
+//! Line-based scene description format.
+//!
+//! Every non-empty line that is not a comment (`#`) holds one directive followed by
+//! `key=value` parameters; vectors are written as comma separated components:
+//!
+//! ```text
+//! image width=400 aspect=16:9
+//! camera look_from=0,0,0 look_at=0,0,-1 up=0,1,0 vfov=90 aperture=0.1 focus_distance=1
+//! camera shutter=0,0.5
+//! background color=0,0,0
+//! texture checks checker scale=0.5 even=0.2,0.3,0.1 odd=0.9,0.9,0.9
+//! texture earth image file=earthmap.png
+//! texture stone noise type=marble scale=4 seed=1
+//! material ground lambertian albedo=checks
+//! material glass dielectric ior=1.5
+//! material gold metal albedo=0.8,0.6,0.2 fuzz=0.3
+//! material lamp light emit=15,15,15 two_sided=false
+//! material mist isotropic albedo=1,1,1
+//! material smoke henyey_greenstein albedo=0.8,0.8,0.8 g=0.6
+//! plane point=0,-0.5,0 normal=0,1,0 material=ground
+//! sphere center=0,0,-1 radius=0.5 material=glass
+//! triangle v0=-1,0,-2 v1=1,0,-2 v2=0,1,-2 material=gold
+//! quad corner=-1,0,-3 u=2,0,0 v=0,1,0 material=gold
+//! disk center=0,1,-3 normal=0,-1,0 radius=0.5 material=gold
+//! box min=1,-0.5,-2 max=2,0.5,-1 material=gold
+//! cylinder base=-2,-0.5,-2 top=-2,0.5,-2 radius=0.3 material=gold
+//! cone base=0,-0.5,-3 apex=0,0.5,-3 radius=0.4 material=gold
+//! torus center=0,0,-2 axis=0,1,0 major=0.5 minor=0.1 material=gold
+//! mesh file=teapot.obj material=gold
+//! object pot mesh file=teapot.obj material=gold
+//! instance pot translate=2,0,-3 rotate=0,1,0,45 scale=0.5
+//! instance pot translate=-2,0,-3 translate_end=-2,0.5,-3 rotate_end=0,1,0,30
+//! sphere center=1,0,-1 center_end=1,0.3,-1 radius=0.5 material=gold
+//! object haze box min=-3,-0.5,-4 max=3,2,0 material=gold
+//! medium haze density=0.2 material=mist
+//! volume min=-1,0,-3 max=1,2,-1 file=smoke.raw resolution=64,64,64 scattering=4 material=mist
+//! ```
+//!
+//! Textures must be declared before the materials that use them, and materials
+//! before the objects that use them. Wherever a texture is expected, a plain color
+//! may be given instead. Texture types are `solid` (`color`), `checker` (`scale`,
+//! `even`, `odd`), `image` (`file`, PNG or PPM) and `noise` (`scale`, optional
+//! `seed`, `type` one of `perlin`, `turbulence` or `marble`).
+//!
+//! A camera `aperture` above zero (the lens diameter) blurs everything away from
+//! the focus plane, which by default passes through `look_at`.
+//!
+//! The background is either one `color` or a vertical gradient from `bottom` to
+//! `top`; without a `background` line the scene is lit by a light blue sky.
+//! `light` materials make any shape an area light that emits on its front side.
+//! Spheres and quads with a light material are also sampled directly, which makes
+//! small lights converge much faster.
+//!
+//! Image and mesh files are resolved relative to the scene file; a mesh's
+//! `material` is only used for faces without a material from the OBJ's own
+//! material library.
+//!
+//! `object` takes a name followed by any shape directive and stores the shape
+//! without adding it to the scene. Each `instance` then places the stored shape,
+//! scaled first (by a number or per axis), then rotated by an angle in degrees
+//! around an axis and finally translated. Instances share the shape's geometry.
+//!
+//! `medium` fills the inside of a closed object with a homogeneous volume of the
+//! given `density` (scattering events per unit length). Its material is a phase
+//! function, `isotropic` or `henyey_greenstein` with asymmetry `g` in (-1, 1); the
+//! object's own material is ignored. To place a medium with `instance`, define it
+//! as an object itself (`object smoke medium haze density=0.2 material=mist`).
+//!
+//! `volume` is a medium whose density varies, read from a raw `file` of
+//! little-endian 32-bit floats with the given `resolution` (x varying fastest,
+//! then y, then z) and stretched over the box from `min` to `max`. The density
+//! scales the `absorption` (default 0) and `scattering` (default 1) coefficients.
+//! Either coefficient can instead follow its own grid of the same resolution,
+//! `absorption_file` or `scattering_file`, so that the albedo varies through the
+//! volume; with both, no density `file` is needed. An optional `emission` grid,
+//! multiplied by `emit`, makes the absorbing parts of the volume glow, like fire;
+//! it needs a positive `absorption`.
+//!
+//! Objects can move over time, from the plain values at time 0 to the `_end`
+//! values at time 1 (`center_end` for spheres; `scale_end`, `rotate_end` and
+//! `translate_end` for instances). The camera `shutter` selects the part of that
+//! interval seen in the image, by default all of it. Moving spheres with a `light`
+//! material are not sampled directly, so they only light the scene through
+//! paths that happen to hit them.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use glam::{DQuat, DVec3};
+
+use crate::aabb::Aabb;
+use crate::bvh;
+use crate::camera::CameraSettings;
+use crate::cone::Cone;
+use crate::cuboid::Cuboid;
+use crate::cylinder::Cylinder;
+use crate::disk::Disk;
+use crate::hittable::{Hittable, HittableList};
+use crate::input;
+use crate::material::{
+    Dielectric, DiffuseLight, HenyeyGreenstein, Isotropic, Lambertian, Material, Metal,
+};
+use crate::medium::{ConstantMedium, GridMedium, GridVolume, VoxelGrid};
+use crate::obj;
+use crate::perlin::Perlin;
+use crate::plane::Plane;
+use crate::quad::Quad;
+use crate::random::Rng;
+use crate::render::Background;
+use crate::sphere::{MovingSphere, Sphere};
+use crate::texture::{Checker, ImageTexture, NoiseKind, NoiseTexture, SolidColor, Texture};
+use crate::torus::Torus;
+use crate::transform::{AnimatedTransform, Keyframe, Transform};
+use crate::triangle::Triangle;
+
+/// Everything a scene file describes.
+pub struct Scene {
+    pub camera: CameraSettings,
+    pub background: Background,
+    pub world: HittableList,
+    /// Copies of the sphere and quad lights in `world`, for sampling them directly.
+    pub lights: HittableList,
+}
+
+/// Error in a scene file, reported with its line.
+#[derive(Debug)]
+pub struct SceneError {
+    /// 1-based line number the error was found on.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "line {}: {}", self.line, self.message);
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+/// Parses a scene from the text of a scene file. Relative paths inside the scene
+/// are resolved against `base_dir`.
+pub fn parse(source: &str, base_dir: &Path) -> Result<Scene, SceneError> {
+    let mut parser = Parser {
+        base_dir,
+        camera: CameraSettings::default(),
+        background: Background::default(),
+        world: HittableList {
+            objects: Vec::new(),
+        },
+        lights: HittableList {
+            objects: Vec::new(),
+        },
+        textures: HashMap::new(),
+        materials: HashMap::new(),
+        emitters: HashSet::new(),
+        objects: HashMap::new(),
+        line: 0,
+        camera_line: 0,
+    };
+    for (index, line) in source.lines().enumerate() {
+        parser.line = index + 1;
+        parser.parse_line(line).map_err(|message| SceneError {
+            line: index + 1,
+            message,
+        })?;
+    }
+    // Checked once the whole camera is known, as it may be set over several lines.
+    check_view(&parser.camera).map_err(|message| SceneError {
+        line: parser.camera_line,
+        message,
+    })?;
+    return Ok(Scene {
+        camera: parser.camera,
+        background: parser.background,
+        world: parser.world,
+        lights: parser.lights,
+    });
+}
+
+struct Parser<'a> {
+    base_dir: &'a Path,
+    camera: CameraSettings,
+    background: Background,
+    world: HittableList,
+    lights: HittableList,
+    textures: HashMap<String, Arc<dyn Texture>>,
+    materials: HashMap<String, Arc<dyn Material>>,
+    /// Names of the `light` materials.
+    emitters: HashSet<String>,
+    /// Shapes declared with `object`, available for instancing.
+    objects: HashMap<String, Arc<dyn Hittable>>,
+    /// Line being parsed.
+    line: usize,
+    /// Last line with a `camera` directive.
+    camera_line: usize,
+}
+
+impl Parser<'_> {
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let line = line.split('#').next().unwrap_or("");
+        let mut tokens = line.split_whitespace();
+        let Some(directive) = tokens.next() else {
+            return Ok(());
+        };
+
+        match directive {
+            "image" => {
+                let mut params = Params::new(tokens)?;
+                if let Some(width) = params.optional("width", parse_int)? {
+                    self.camera.image_width = width;
+                }
+                if let Some(aspect) = params.optional("aspect", parse_aspect)? {
+                    self.camera.aspect_ratio = aspect;
+                }
+                if let Some(height) = params.optional("height", parse_int)? {
+                    self.camera.image_height = Some(height);
+                }
+                params.finish()?;
+            }
+            "camera" => {
+                let mut params = Params::new(tokens)?;
+                if let Some(look_from) = params.optional("look_from", parse_vec3)? {
+                    self.camera.look_from = look_from;
+                }
+                if let Some(look_at) = params.optional("look_at", parse_vec3)? {
+                    self.camera.look_at = look_at;
+                }
+                if let Some(up) = params.optional("up", parse_vec3)? {
+                    self.camera.vup = up;
+                }
+                if let Some(vfov) = params.optional("vfov", parse_fov)? {
+                    self.camera.vfov = vfov;
+                }
+                if let Some(aperture) = params.optional("aperture", parse_non_negative)? {
+                    self.camera.aperture = aperture;
+                }
+                if let Some(focus_distance) = params.optional("focus_distance", parse_positive)? {
+                    self.camera.focus_distance = Some(focus_distance);
+                }
+                if let Some((open, close)) = params.optional("shutter", parse_interval)? {
+                    self.camera.shutter_open = open;
+                    self.camera.shutter_close = close;
+                }
+                params.finish()?;
+                self.camera_line = self.line;
+            }
+            "background" => {
+                let mut params = Params::new(tokens)?;
+                let color = params.optional("color", parse_vec3)?;
+                let bottom = params.optional("bottom", parse_vec3)?;
+                let top = params.optional("top", parse_vec3)?;
+                params.finish()?;
+                self.background = match (color, bottom, top) {
+                    (Some(color), None, None) => Background::Solid(color),
+                    (None, Some(bottom), Some(top)) => Background::Gradient { bottom, top },
+                    _ => return Err("background needs either color or bottom and top".to_string()),
+                };
+            }
+            "texture" => {
+                let name = tokens.next().ok_or("texture needs a name")?;
+                let kind = tokens.next().ok_or("texture needs a type")?;
+                let mut params = Params::new(tokens)?;
+                let texture: Arc<dyn Texture> = match kind {
+                    "solid" => Arc::new(SolidColor {
+                        color: params.required("color", parse_vec3)?,
+                    }),
+                    "checker" => Arc::new(Checker {
+                        scale: params.required("scale", parse_positive)?,
+                        even: self.texture(params.required("even", parse_name)?)?,
+                        odd: self.texture(params.required("odd", parse_name)?)?,
+                    }),
+                    "image" => {
+                        let file = params.required("file", parse_name)?;
+                        let image = input::load(&self.base_dir.join(file))
+                            .map_err(|err| format!("failed to load image: {}", err))?;
+                        Arc::new(ImageTexture { image })
+                    }
+                    "noise" => {
+                        let seed = params.optional("seed", parse_seed)?.unwrap_or(0);
+                        Arc::new(NoiseTexture {
+                            noise: Perlin::new(&mut Rng::new(seed)),
+                            scale: params.optional("scale", parse_positive)?.unwrap_or(1.0),
+                            kind: params
+                                .optional("type", parse_noise_kind)?
+                                .unwrap_or(NoiseKind::Perlin),
+                        })
+                    }
+                    _ => return Err(format!("unknown texture type '{}'", kind)),
+                };
+                params.finish()?;
+                if self.textures.insert(name.to_string(), texture).is_some() {
+                    return Err(format!("texture '{}' is already defined", name));
+                }
+            }
+            "material" => {
+                let name = tokens.next().ok_or("material needs a name")?;
+                let kind = tokens.next().ok_or("material needs a type")?;
+                let mut params = Params::new(tokens)?;
+                let material: Arc<dyn Material> = match kind {
+                    "lambertian" => Arc::new(Lambertian {
+                        albedo: self.texture(params.required("albedo", parse_name)?)?,
+                    }),
+                    "metal" => Arc::new(Metal {
+                        albedo: params.required("albedo", parse_vec3)?,
+                        fuzz: params.optional("fuzz", parse_float)?.unwrap_or(0.0),
+                    }),
+                    "dielectric" => Arc::new(Dielectric {
+                        refraction_index: params.required("ior", parse_float)?,
+                    }),
+                    "isotropic" => Arc::new(Isotropic {
+                        albedo: self.texture(params.required("albedo", parse_name)?)?,
+                    }),
+                    "henyey_greenstein" => Arc::new(HenyeyGreenstein {
+                        albedo: self.texture(params.required("albedo", parse_name)?)?,
+                        g: params.required("g", parse_asymmetry)?,
+                    }),
+                    "light" => Arc::new(DiffuseLight {
+                        emit: self.texture(params.required("emit", parse_name)?)?,
+                        two_sided: params.optional("two_sided", parse_bool)?.unwrap_or(false),
+                    }),
+                    _ => return Err(format!("unknown material type '{}'", kind)),
+                };
+                params.finish()?;
+                if kind == "light" {
+                    self.emitters.insert(name.to_string());
+                }
+                if self.materials.insert(name.to_string(), material).is_some() {
+                    return Err(format!("material '{}' is already defined", name));
+                }
+            }
+            "sphere" => {
+                let mut params = Params::new(tokens)?;
+                let center = params.required("center", parse_vec3)?;
+                let radius = params.required("radius", parse_float)?;
+                let material = params.required("material", parse_name)?;
+                // A negative radius is allowed, it turns the normals inwards.
+                if radius == 0.0 {
+                    return Err("sphere radius must not be zero".to_string());
+                }
+                if let Some(center_end) = params.optional("center_end", parse_vec3)? {
+                    let sphere = MovingSphere {
+                        center0: center,
+                        center1: center_end,
+                        radius,
+                        material: self.material(material)?,
+                    };
+                    return self.push(params, sphere);
+                }
+                let sphere = Sphere {
+                    center,
+                    radius,
+                    material: self.material(material)?,
+                };
+                if self.emitters.contains(material) {
+                    self.lights.objects.push(Box::new(sphere.clone()));
+                }
+                self.push(params, sphere)?;
+            }
+            "triangle" => {
+                let mut params = Params::new(tokens)?;
+                let vertices = [
+                    params.required("v0", parse_vec3)?,
+                    params.required("v1", parse_vec3)?,
+                    params.required("v2", parse_vec3)?,
+                ];
+                let normal = (vertices[1] - vertices[0]).cross(vertices[2] - vertices[0]);
+                if normal.length_squared() == 0.0 {
+                    return Err("triangle vertices must not be collinear".to_string());
+                }
+                let triangle = Triangle {
+                    vertices,
+                    normals: None,
+                    uvs: None,
+                    material: self.material(params.required("material", parse_name)?)?,
+                };
+                params.finish()?;
+                self.world.objects.push(Box::new(triangle));
+            }
+            "plane" => {
+                let mut params = Params::new(tokens)?;
+                let plane = Plane {
+                    point: params.required("point", parse_vec3)?,
+                    normal: params.required("normal", parse_direction)?,
+                    material: self.material(params.required("material", parse_name)?)?,
+                };
+                self.push(params, plane)?;
+            }
+            "quad" => {
+                let mut params = Params::new(tokens)?;
+                let corner = params.required("corner", parse_vec3)?;
+                let u = params.required("u", parse_vec3)?;
+                let v = params.required("v", parse_vec3)?;
+                let material = params.required("material", parse_name)?;
+                if u.cross(v).length_squared() == 0.0 {
+                    return Err("quad edges u and v must not be parallel".to_string());
+                }
+                let quad = Quad {
+                    corner,
+                    u,
+                    v,
+                    material: self.material(material)?,
+                };
+                if self.emitters.contains(material) {
+                    self.lights.objects.push(Box::new(quad.clone()));
+                }
+                self.push(params, quad)?;
+            }
+            "disk" => {
+                let mut params = Params::new(tokens)?;
+                let disk = Disk {
+                    center: params.required("center", parse_vec3)?,
+                    normal: params.required("normal", parse_direction)?,
+                    radius: params.required("radius", parse_positive)?,
+                    material: self.material(params.required("material", parse_name)?)?,
+                };
+                self.push(params, disk)?;
+            }
+            "box" => {
+                let mut params = Params::new(tokens)?;
+                let a = params.required("min", parse_vec3)?;
+                let b = params.required("max", parse_vec3)?;
+                let cuboid = Cuboid {
+                    min: a.min(b),
+                    max: a.max(b),
+                    material: self.material(params.required("material", parse_name)?)?,
+                };
+                self.push(params, cuboid)?;
+            }
+            "cylinder" => {
+                let mut params = Params::new(tokens)?;
+                let cylinder = Cylinder {
+                    base: params.required("base", parse_vec3)?,
+                    top: params.required("top", parse_vec3)?,
+                    radius: params.required("radius", parse_positive)?,
+                    material: self.material(params.required("material", parse_name)?)?,
+                };
+                if cylinder.base == cylinder.top {
+                    return Err("cylinder base and top must differ".to_string());
+                }
+                self.push(params, cylinder)?;
+            }
+            "cone" => {
+                let mut params = Params::new(tokens)?;
+                let cone = Cone {
+                    base: params.required("base", parse_vec3)?,
+                    apex: params.required("apex", parse_vec3)?,
+                    radius: params.required("radius", parse_positive)?,
+                    material: self.material(params.required("material", parse_name)?)?,
+                };
+                if cone.base == cone.apex {
+                    return Err("cone base and apex must differ".to_string());
+                }
+                self.push(params, cone)?;
+            }
+            "torus" => {
+                let mut params = Params::new(tokens)?;
+                let torus = Torus {
+                    center: params.required("center", parse_vec3)?,
+                    axis: params
+                        .optional("axis", parse_direction)?
+                        .unwrap_or(DVec3::Y),
+                    major_radius: params.required("major", parse_positive)?,
+                    minor_radius: params.required("minor", parse_positive)?,
+                    material: self.material(params.required("material", parse_name)?)?,
+                };
+                if torus.minor_radius >= torus.major_radius {
+                    return Err("torus minor radius must be smaller than major".to_string());
+                }
+                self.push(params, torus)?;
+            }
+            "mesh" => {
+                let mut params = Params::new(tokens)?;
+                let file = params.required("file", parse_name)?;
+                let material = match params.optional("material", parse_name)? {
+                    Some(name) => self.material(name)?,
+                    None => Arc::new(Lambertian::new(DVec3::splat(0.8))),
+                };
+                params.finish()?;
+                let model = obj::load_obj(&self.base_dir.join(file), material)
+                    .map_err(|err| format!("failed to load mesh: {}", err))?;
+                self.world.objects.extend(model.into_hittables());
+            }
+            "object" => {
+                let name = tokens.next().ok_or("object needs a name")?;
+                let shape: Vec<&str> = tokens.collect();
+                match shape.first() {
+                    Some(
+                        &"image" | &"camera" | &"background" | &"texture" | &"material" | &"object"
+                        | &"instance",
+                    ) => {
+                        return Err(format!("'{}' cannot be used as an object", shape[0]));
+                    }
+                    Some(_) => {}
+                    None => return Err("object needs a shape".to_string()),
+                }
+                // Parse the shape as usual, but into an empty list instead of the world.
+                // Lights inside objects are not sampled directly since the copy in
+                // `lights` would not follow the instances' transforms.
+                let world = std::mem::take(&mut self.world.objects);
+                let lights = std::mem::take(&mut self.lights.objects);
+                let result = self.parse_line(&shape.join(" "));
+                let objects = std::mem::replace(&mut self.world.objects, world);
+                self.lights.objects = lights;
+                result?;
+                if objects.is_empty() {
+                    return Err(format!("object '{}' contains no geometry", name));
+                }
+                let object: Arc<dyn Hittable> = Arc::from(bvh::accelerate(objects));
+                if self.objects.insert(name.to_string(), object).is_some() {
+                    return Err(format!("object '{}' is already defined", name));
+                }
+            }
+            "medium" => {
+                let name = tokens.next().ok_or("medium needs a boundary object name")?;
+                let boundary = match self.objects.get(name) {
+                    Some(object) => object.clone(),
+                    None => return Err(format!("unknown object '{}'", name)),
+                };
+                let mut params = Params::new(tokens)?;
+                let medium = ConstantMedium {
+                    boundary,
+                    density: params.required("density", parse_positive)?,
+                    phase_function: self.material(params.required("material", parse_name)?)?,
+                };
+                self.push(params, medium)?;
+            }
+            "volume" => {
+                let mut params = Params::new(tokens)?;
+                let a = params.required("min", parse_vec3)?;
+                let b = params.required("max", parse_vec3)?;
+                if !a.cmpne(b).all() {
+                    return Err("volume min and max must differ along every axis".to_string());
+                }
+                let resolution = params.required("resolution", parse_resolution)?;
+                let load = |file: &str| {
+                    return VoxelGrid::load(&self.base_dir.join(file), resolution)
+                        .map_err(|err| format!("failed to load grid '{}': {}", file, err));
+                };
+                let density = match params.optional("file", parse_name)? {
+                    Some(file) => Some(Arc::new(load(file)?)),
+                    None => None,
+                };
+                let mut grid = |key: &str| {
+                    return match params.optional(key, parse_name)? {
+                        Some(file) => Ok(Arc::new(load(file)?)),
+                        None => density
+                            .clone()
+                            .ok_or_else(|| format!("volume needs a density file or an {}", key)),
+                    };
+                };
+                let absorption_grid = grid("absorption_file")?;
+                let scattering_grid = grid("scattering_file")?;
+                let emission = match params.optional("emission", parse_name)? {
+                    Some(file) => Some(load(file)?),
+                    None => None,
+                };
+                let volume = GridVolume {
+                    bounds: Aabb::new(a, b),
+                    absorption_grid,
+                    absorption: params
+                        .optional("absorption", parse_non_negative)?
+                        .unwrap_or(0.0),
+                    scattering_grid,
+                    scattering: params
+                        .optional("scattering", parse_non_negative)?
+                        .unwrap_or(1.0),
+                    emission,
+                    emission_color: params.optional("emit", parse_vec3)?.unwrap_or(DVec3::ONE),
+                    phase_function: self.material(params.required("material", parse_name)?)?,
+                };
+                if volume.emission.is_some() && volume.absorption <= 0.0 {
+                    return Err("volume emission needs a positive absorption".to_string());
+                }
+                if !volume.majorant().is_finite() {
+                    return Err("volume absorption and scattering are too large".to_string());
+                }
+                self.push(params, GridMedium::new(volume))?;
+            }
+            "instance" => {
+                let name = tokens.next().ok_or("instance needs an object name")?;
+                let object = match self.objects.get(name) {
+                    Some(object) => object.clone(),
+                    None => return Err(format!("unknown object '{}'", name)),
+                };
+                let mut params = Params::new(tokens)?;
+                let start = Keyframe {
+                    scale: params.optional("scale", parse_scale)?.unwrap_or(DVec3::ONE),
+                    rotation: params
+                        .optional("rotate", parse_rotation)?
+                        .unwrap_or(DQuat::IDENTITY),
+                    translation: params
+                        .optional("translate", parse_vec3)?
+                        .unwrap_or(DVec3::ZERO),
+                };
+                let scale_end = params.optional("scale_end", parse_scale)?;
+                let rotation_end = params.optional("rotate_end", parse_rotation)?;
+                let translation_end = params.optional("translate_end", parse_vec3)?;
+                if scale_end.is_none() && rotation_end.is_none() && translation_end.is_none() {
+                    return self.push(params, Transform::new(object, start.matrix()));
+                }
+                let end = Keyframe {
+                    scale: scale_end.unwrap_or(start.scale),
+                    rotation: rotation_end.unwrap_or(start.rotation),
+                    translation: translation_end.unwrap_or(start.translation),
+                };
+                if !(start.scale * end.scale).cmpgt(DVec3::ZERO).all() {
+                    return Err("scale and scale_end must have the same signs".to_string());
+                }
+                self.push(params, AnimatedTransform::new(object, start, end))?;
+            }
+            _ => return Err(format!("unknown directive '{}'", directive)),
+        }
+        return Ok(());
+    }
+
+    fn push(&mut self, params: Params, object: impl Hittable + 'static) -> Result<(), String> {
+        params.finish()?;
+        self.world.objects.push(Box::new(object));
+        return Ok(());
+    }
+
+    /// A declared texture, or a constant one when `value` is a color.
+    fn texture(&self, value: &str) -> Result<Arc<dyn Texture>, String> {
+        if value.contains(',') {
+            let color =
+                parse_vec3(value).map_err(|err| format!("invalid color '{}': {}", value, err))?;
+            return Ok(Arc::new(SolidColor { color }));
+        }
+        return match self.textures.get(value) {
+            Some(texture) => Ok(texture.clone()),
+            None => Err(format!("unknown texture '{}'", value)),
+        };
+    }
+
+    fn material(&self, name: &str) -> Result<Arc<dyn Material>, String> {
+        return match self.materials.get(name) {
+            Some(material) => Ok(material.clone()),
+            None => Err(format!("unknown material '{}'", name)),
+        };
+    }
+}
+
+/// Rejects cameras without a view direction or with `up` along it, for which no
+/// image plane can be set up.
+fn check_view(camera: &CameraSettings) -> Result<(), String> {
+    let view = camera.look_at - camera.look_from;
+    if view.length_squared() == 0.0 {
+        return Err("camera look_from and look_at must differ".to_string());
+    }
+    if camera
+        .vup
+        .normalize_or_zero()
+        .cross(view.normalize())
+        .length()
+        < 1e-9
+    {
+        return Err("camera up must not be zero or parallel to the view direction".to_string());
+    }
+    return Ok(());
+}
+
+/// The `key=value` parameters of one directive. Each parameter has to be consumed
+/// exactly once so that typos are reported instead of silently ignored.
+struct Params<'a> {
+    values: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Params<'a> {
+    fn new(tokens: impl Iterator<Item = &'a str>) -> Result<Params<'a>, String> {
+        let mut values: Vec<(&str, &str)> = Vec::new();
+        for token in tokens {
+            let Some((key, value)) = token.split_once('=') else {
+                return Err(format!("expected key=value, found '{}'", token));
+            };
+            if values.iter().any(|(k, _)| *k == key) {
+                return Err(format!("parameter '{}' given twice", key));
+            }
+            values.push((key, value));
+        }
+        return Ok(Params { values });
+    }
+
+    fn optional<T>(
+        &mut self,
+        key: &str,
+        parse: fn(&'a str) -> Result<T, String>,
+    ) -> Result<Option<T>, String> {
+        let Some(index) = self.values.iter().position(|(k, _)| *k == key) else {
+            return Ok(None);
+        };
+        let (_, value) = self.values.remove(index);
+        return match parse(value) {
+            Ok(parsed) => Ok(Some(parsed)),
+            Err(err) => Err(format!("invalid {} '{}': {}", key, value, err)),
+        };
+    }
+
+    fn required<T>(
+        &mut self,
+        key: &str,
+        parse: fn(&'a str) -> Result<T, String>,
+    ) -> Result<T, String> {
+        return self
+            .optional(key, parse)?
+            .ok_or_else(|| format!("missing parameter '{}'", key));
+    }
+
+    fn finish(self) -> Result<(), String> {
+        return match self.values.first() {
+            Some((key, _)) => Err(format!("unknown parameter '{}'", key)),
+            None => Ok(()),
+        };
+    }
+}
+
+fn parse_name(value: &str) -> Result<&str, String> {
+    return Ok(value);
+}
+
+fn parse_float(value: &str) -> Result<f64, String> {
+    let parsed: f64 = value.parse().map_err(|_| "expected a number".to_string())?;
+    if !parsed.is_finite() {
+        return Err("expected a finite number".to_string());
+    }
+    return Ok(parsed);
+}
+
+fn parse_positive(value: &str) -> Result<f64, String> {
+    let parsed = parse_float(value)?;
+    if parsed <= 0.0 {
+        return Err("expected a positive number".to_string());
+    }
+    return Ok(parsed);
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    return match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err("expected true or false".to_string()),
+    };
+}
+
+fn parse_non_negative(value: &str) -> Result<f64, String> {
+    let parsed = parse_float(value)?;
+    if parsed < 0.0 {
+        return Err("expected a non-negative number".to_string());
+    }
+    return Ok(parsed);
+}
+
+/// Two numbers `start,end` in [0, 1] with start not after end.
+fn parse_interval(value: &str) -> Result<(f64, f64), String> {
+    let Some((start, end)) = value.split_once(',') else {
+        return Err("expected two comma separated numbers".to_string());
+    };
+    let (start, end) = (parse_float(start)?, parse_float(end)?);
+    if !(0.0 <= start && start <= end && end <= 1.0) {
+        return Err("expected 0 <= start <= end <= 1".to_string());
+    }
+    return Ok((start, end));
+}
+
+/// Henyey-Greenstein asymmetry, strictly between -1 and 1.
+fn parse_asymmetry(value: &str) -> Result<f64, String> {
+    let parsed = parse_float(value)?;
+    if parsed.abs() >= 1.0 {
+        return Err("expected a number between -1 and 1".to_string());
+    }
+    return Ok(parsed);
+}
+
+/// Vertical field of view in degrees, strictly between 0 and 180.
+fn parse_fov(value: &str) -> Result<f64, String> {
+    let parsed = parse_float(value)?;
+    if parsed <= 0.0 || parsed >= 180.0 {
+        return Err("expected an angle between 0 and 180 degrees".to_string());
+    }
+    return Ok(parsed);
+}
+
+fn parse_seed(value: &str) -> Result<u64, String> {
+    return value
+        .parse()
+        .map_err(|_| "expected a non-negative integer".to_string());
+}
+
+fn parse_noise_kind(value: &str) -> Result<NoiseKind, String> {
+    return match value {
+        "perlin" => Ok(NoiseKind::Perlin),
+        "turbulence" => Ok(NoiseKind::Turbulence),
+        "marble" => Ok(NoiseKind::Marble),
+        _ => Err("expected perlin, turbulence or marble".to_string()),
+    };
+}
+
+fn parse_int(value: &str) -> Result<i32, String> {
+    return match value.parse() {
+        Ok(parsed) if parsed > 0 => Ok(parsed),
+        _ => Err("expected a positive integer".to_string()),
+    };
+}
+
+/// Largest accepted voxel grid, four gigabytes of floats.
+const MAX_VOXELS: usize = 1 << 30;
+
+/// Voxel counts `x,y,z` of a grid.
+fn parse_resolution(value: &str) -> Result<[usize; 3], String> {
+    let components: Vec<&str> = value.split(',').collect();
+    let [x, y, z] = components[..] else {
+        return Err("expected three comma separated integers".to_string());
+    };
+    let resolution = [
+        parse_int(x)? as usize,
+        parse_int(y)? as usize,
+        parse_int(z)? as usize,
+    ];
+    let voxels = resolution
+        .iter()
+        .try_fold(1usize, |voxels, &n| voxels.checked_mul(n));
+    if voxels.is_none_or(|voxels| voxels > MAX_VOXELS) {
+        return Err(format!("expected at most {} voxels in total", MAX_VOXELS));
+    }
+    return Ok(resolution);
+}
+
+fn parse_vec3(value: &str) -> Result<DVec3, String> {
+    let components: Vec<&str> = value.split(',').collect();
+    let [x, y, z] = components[..] else {
+        return Err("expected three comma separated numbers".to_string());
+    };
+    return Ok(DVec3::new(
+        parse_float(x)?,
+        parse_float(y)?,
+        parse_float(z)?,
+    ));
+}
+
+/// A vector that is used as a direction and therefore must not be zero.
+fn parse_direction(value: &str) -> Result<DVec3, String> {
+    let parsed = parse_vec3(value)?;
+    if parsed == DVec3::ZERO {
+        return Err("expected a non-zero direction".to_string());
+    }
+    return Ok(parsed);
+}
+
+/// Either one factor for all axes or one per axis; zero would flatten the object.
+fn parse_scale(value: &str) -> Result<DVec3, String> {
+    let scale = match value.contains(',') {
+        true => parse_vec3(value)?,
+        false => DVec3::splat(parse_float(value)?),
+    };
+    if scale.x * scale.y * scale.z == 0.0 {
+        return Err("scale factors must not be zero".to_string());
+    }
+    return Ok(scale);
+}
+
+/// Rotation written as `axis_x,axis_y,axis_z,degrees`.
+fn parse_rotation(value: &str) -> Result<DQuat, String> {
+    let Some((axis, degrees)) = value.rsplit_once(',') else {
+        return Err("expected an axis and an angle in degrees".to_string());
+    };
+    let axis = parse_direction(axis)?;
+    let degrees = parse_float(degrees)?;
+    return Ok(DQuat::from_axis_angle(
+        axis.normalize(),
+        degrees.to_radians(),
+    ));
+}
+
+/// Either a plain ratio (`1.5`) or `width:height` (`16:9`).
+fn parse_aspect(value: &str) -> Result<f64, String> {
+    let aspect = match value.split_once(':') {
+        Some((w, h)) => parse_float(w)? / parse_float(h)?,
+        None => parse_float(value)?,
+    };
+    if !(aspect.is_finite() && aspect > 0.0) {
+        return Err("expected a positive ratio".to_string());
+    }
+    return Ok(aspect);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::hittable::HitRecord;
+    use crate::ray::Ray;
+
+    fn parse_str(source: &str) -> Result<Scene, SceneError> {
+        return parse(source, Path::new(""));
+    }
+
+    fn error(source: &str) -> (usize, String) {
+        let err = parse_str(source).err().expect("scene should be rejected");
+        return (err.line, err.message);
+    }
+
+    /// Parameter at which a ray from `origin` along `dir` first hits the scene.
+    fn first_hit(scene: &Scene, origin: DVec3, dir: DVec3) -> Option<f64> {
+        let ray = Ray {
+            origin,
+            dir,
+            time: 0.0,
+        };
+        let mut rec = HitRecord {
+            p: DVec3::ZERO,
+            normal: DVec3::ZERO,
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: false,
+            material: None,
+        };
+        return if scene.world.hit(&ray, 0.001, f64::INFINITY, &mut rec) {
+            Some(rec.t)
+        } else {
+            None
+        };
+    }
+
+    const MATERIAL: &str = "material white lambertian albedo=0.8,0.8,0.8\n";
+
+    #[test]
+    fn parses_shapes_materials_and_settings() {
+        let scene = parse_str(&format!(
+            "# comment\n\nimage width=64 aspect=2:1\ncamera look_from=0,0,5 vfov=30\n\
+             background color=0,0,0\n{}material lamp light emit=4,4,4\n\
+             sphere center=0,0,0 radius=1 material=white # trailing comment\n\
+             sphere center=0,3,0 radius=0.5 material=lamp\n",
+            MATERIAL
+        ))
+        .unwrap();
+        assert_eq!(scene.camera.image_width, 64);
+        assert_eq!(scene.camera.aspect_ratio, 2.0);
+        assert_eq!(scene.camera.look_from, DVec3::new(0.0, 0.0, 5.0));
+        assert_eq!(scene.background, Background::Solid(DVec3::ZERO));
+        assert_eq!(scene.world.objects.len(), 2);
+        assert_eq!(scene.lights.objects.len(), 1);
+        assert_eq!(
+            first_hit(&scene, DVec3::new(0.0, 0.0, 5.0), DVec3::NEG_Z),
+            Some(4.0)
+        );
+    }
+
+    #[test]
+    fn errors_carry_their_line_number() {
+        assert_eq!(
+            error("image width=10\n\nsphere center=0,0,0 radius=1 material=gold\n"),
+            (3, "unknown material 'gold'".to_string())
+        );
+        assert_eq!(
+            error("\nteapot size=3"),
+            (2, "unknown directive 'teapot'".to_string())
+        );
+    }
+
+    #[test]
+    fn parameters_must_be_known_complete_and_valid() {
+        let cases = [
+            (
+                "sphere center=0,0,0 radius=1 material=white colour=red",
+                "unknown parameter 'colour'",
+            ),
+            (
+                "sphere center=0,0,0 radius=1 radius=2 material=white",
+                "parameter 'radius' given twice",
+            ),
+            (
+                "sphere center=0,0,0 material=white",
+                "missing parameter 'radius'",
+            ),
+            (
+                "sphere center=0,0 radius=1 material=white",
+                "invalid center '0,0': expected three comma separated numbers",
+            ),
+            (
+                "sphere center=0,0,0 radius material=white",
+                "expected key=value, found 'radius'",
+            ),
+            (
+                "image width=-4",
+                "invalid width '-4': expected a positive integer",
+            ),
+        ];
+        for (line, message) in cases {
+            assert_eq!(
+                error(&format!("{}{}", MATERIAL, line)),
+                (2, message.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_degenerate_spheres_and_triangles() {
+        let cases = [
+            (
+                "sphere center=0,0,0 radius=0 material=white",
+                "sphere radius must not be zero",
+            ),
+            (
+                "triangle v0=0,0,0 v1=1,1,1 v2=3,3,3 material=white",
+                "triangle vertices must not be collinear",
+            ),
+            (
+                "triangle v0=0,0,0 v1=0,0,0 v2=0,1,0 material=white",
+                "triangle vertices must not be collinear",
+            ),
+        ];
+        for (line, message) in cases {
+            assert_eq!(
+                error(&format!("{}{}", MATERIAL, line)),
+                (2, message.to_string())
+            );
+        }
+        assert!(parse_str(&format!(
+            "{}sphere center=0,0,0 radius=-1 material=white
+\
+             triangle v0=0,0,0 v1=1,0,0 v2=0,1,0 material=white",
+            MATERIAL
+        ))
+        .is_ok());
+    }
+
+    #[test]
+    fn rejects_degenerate_tori() {
+        let torus = "torus center=0,0,0 major=1 minor=0.25 material=white";
+        assert!(parse_str(&format!("{}{}", MATERIAL, torus)).is_ok());
+        for bad in ["minor=1", "minor=2", "minor=0"] {
+            let line = torus.replace("minor=0.25", bad);
+            assert!(
+                parse_str(&format!("{}{}", MATERIAL, line)).is_err(),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn camera_needs_a_view_direction_and_a_distinct_up() {
+        assert_eq!(
+            error("camera look_from=1,2,3 look_at=1,2,3\nimage width=10\n"),
+            (1, "camera look_from and look_at must differ".to_string())
+        );
+        assert_eq!(
+            error("image width=10\ncamera look_from=0,5,0 look_at=0,0,0"),
+            (
+                2,
+                "camera up must not be zero or parallel to the view direction".to_string()
+            )
+        );
+        assert!(error("camera up=0,0,0").1.contains("parallel"));
+        for vfov in ["0", "-10", "180", "270"] {
+            assert_eq!(
+                error(&format!("image width=10\ncamera vfov={}", vfov)),
+                (
+                    2,
+                    format!(
+                        "invalid vfov '{}': expected an angle between 0 and 180 degrees",
+                        vfov
+                    )
+                )
+            );
+        }
+        assert!(parse_str("camera vfov=179.5").is_ok());
+        // Only the final camera has to be valid.
+        assert!(parse_str("camera look_from=0,0,-1\ncamera look_at=0,0,-5").is_ok());
+    }
+
+    #[test]
+    fn objects_are_only_visible_through_instances() {
+        let source = format!(
+            "{}object ball sphere center=0,0,0 radius=1 material=white\n",
+            MATERIAL
+        );
+        let scene = parse_str(&source).unwrap();
+        assert!(scene.world.objects.is_empty());
+
+        let scene = parse_str(&format!(
+            "{}instance ball translate=3,0,0 scale=2\n",
+            source
+        ))
+        .unwrap();
+        assert_eq!(
+            first_hit(&scene, DVec3::new(3.0, 0.0, 5.0), DVec3::NEG_Z),
+            Some(3.0)
+        );
+        assert_eq!(
+            first_hit(&scene, DVec3::new(0.0, 0.0, 5.0), DVec3::NEG_Z),
+            None
+        );
+
+        assert_eq!(
+            error(&format!("{}instance cube", source)).1,
+            "unknown object 'cube'"
+        );
+        assert_eq!(
+            error(&format!(
+                "{}object ball box min=0,0,0 max=1,1,1 material=white",
+                source
+            ))
+            .1,
+            "object 'ball' is already defined"
+        );
+        assert_eq!(
+            error("object view camera vfov=20").1,
+            "'camera' cannot be used as an object"
+        );
+    }
+
+    #[test]
+    fn media_fill_declared_objects() {
+        let source = format!(
+            "{}material mist isotropic albedo=1,1,1\n\
+             object fog box min=-1,-1,-1 max=1,1,1 material=white\n\
+             medium fog density=1000 material=mist\n",
+            MATERIAL
+        );
+        let scene = parse_str(&source).unwrap();
+        let t = first_hit(&scene, DVec3::new(0.0, 0.0, 5.0), DVec3::NEG_Z).unwrap();
+        assert!((4.0..4.1).contains(&t), "{}", t);
+        assert_eq!(
+            error(&format!("{}medium haze density=1 material=mist", source)).1,
+            "unknown object 'haze'"
+        );
+        assert!(error(&format!(
+            "{}material smoke henyey_greenstein albedo=1,1,1 g=1",
+            source
+        ))
+        .1
+        .contains("between -1 and 1"));
+    }
+
+    #[test]
+    fn volumes_load_voxel_grids() {
+        let dir = std::env::temp_dir();
+        let file = format!("scene-test-{}.raw", std::process::id());
+        let bytes: Vec<u8> = [1000.0f32; 8]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        fs::write(dir.join(&file), bytes).unwrap();
+        let volume = format!(
+            "{}material mist isotropic albedo=1,1,1\n\
+             volume min=-1,-1,-1 max=1,1,1 file={} resolution=2,2,2 material=mist\n",
+            MATERIAL, file
+        );
+        let scene = parse(&volume, &dir);
+        let wrong_size = parse(&volume.replace("2,2,2", "2,2,3"), &dir);
+        let dense = parse(&volume.replace("2,2,2", "2,2,2 scattering=1e308"), &dir);
+        let separate = volume.replace(
+            &format!("file={}", file),
+            &format!("absorption_file={0} scattering_file={0}", file),
+        );
+        let separate = parse(&separate, &dir);
+        let no_density = parse(&volume.replace("file=", "scattering_file="), &dir);
+        let dark = parse(
+            &volume.replace("2,2,2", &format!("2,2,2 emission={}", file)),
+            &dir,
+        );
+        fs::remove_file(dir.join(&file)).unwrap();
+        let scene = scene.unwrap();
+        assert_eq!(scene.world.objects.len(), 1);
+        let t = first_hit(&scene, DVec3::new(0.0, 0.0, 5.0), DVec3::NEG_Z).unwrap();
+        assert!((4.0..4.1).contains(&t), "{}", t);
+        assert!(wrong_size
+            .err()
+            .unwrap()
+            .message
+            .contains("failed to load grid"));
+        assert!(dense.err().unwrap().message.contains("too large"));
+        let t = first_hit(&separate.unwrap(), DVec3::new(0.0, 0.0, 5.0), DVec3::NEG_Z).unwrap();
+        assert!((4.0..4.1).contains(&t), "{}", t);
+        assert_eq!(
+            no_density.err().unwrap().message,
+            "volume needs a density file or an absorption_file"
+        );
+        assert_eq!(
+            dark.err().unwrap().message,
+            "volume emission needs a positive absorption"
+        );
+        let huge = volume.replace("2,2,2", "2000000000,2000000000,2000000000");
+        assert!(error(&huge).1.contains("at most"));
+    }
+}
@@ -0,0 +1,467 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use glam::DVec3;
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::random::Rng;
+use crate::ray::Ray;
+
+/// Sphere given by its center and radius.
+#[derive(Clone)]
+pub struct Sphere {
+    pub center: DVec3,
+    pub radius: f64,
+    pub material: Arc<dyn Material>,
+}
+
+impl Sphere {
+    /// Cosine of the half-angle of the cone the sphere covers as seen from `origin`,
+    /// or `None` when `origin` is inside the sphere.
+    fn cos_theta_max(&self, origin: DVec3) -> Option<f64> {
+        let distance_squared = (self.center - origin).length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return None;
+        }
+        return Some((1.0 - radius_squared / distance_squared).sqrt());
+    }
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        return hit_sphere(
+            self.center,
+            self.radius,
+            &self.material,
+            ray,
+            t_min,
+            t_max,
+            rec,
+        );
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let extent = DVec3::splat(self.radius.abs());
+        return Aabb::new(self.center - extent, self.center + extent);
+    }
+
+    /// Uniform sampling of the cone of directions that see the sphere from `origin`.
+    /// Points inside the sphere cannot be handled this way and get a density of zero.
+    fn pdf_value(&self, origin: DVec3, direction: DVec3) -> f64 {
+        let Some(cos_theta_max) = self.cos_theta_max(origin) else {
+            return 0.0;
+        };
+        let mut rec = HitRecord {
+            p: DVec3::ZERO,
+            normal: DVec3::ZERO,
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: false,
+            material: None,
+        };
+        let ray = Ray {
+            origin,
+            dir: direction,
+            time: 0.0,
+        };
+        if !self.hit(&ray, 0.001, f64::INFINITY, &mut rec) {
+            return 0.0;
+        }
+        let solid_angle = 2.0 * PI * (1.0 - cos_theta_max);
+        return 1.0 / solid_angle;
+    }
+
+    fn random(&self, origin: DVec3, rng: &mut Rng) -> DVec3 {
+        let Some(cos_theta_max) = self.cos_theta_max(origin) else {
+            return DVec3::X;
+        };
+        let r1 = rng.next_f64();
+        let r2 = rng.next_f64();
+        let z = 1.0 + r2 * (cos_theta_max - 1.0);
+        let phi = 2.0 * PI * r1;
+        let sin_theta = (1.0 - z * z).max(0.0).sqrt();
+        let local = DVec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z);
+        return Onb::from_w(self.center - origin).to_world(local);
+    }
+}
+
+/// Sphere moving in a straight line from `center0` at time 0 to `center1` at
+/// time 1, seen blurred when the camera shutter is open during the motion.
+pub struct MovingSphere {
+    pub center0: DVec3,
+    pub center1: DVec3,
+    pub radius: f64,
+    pub material: Arc<dyn Material>,
+}
+
+impl MovingSphere {
+    pub fn center(&self, time: f64) -> DVec3 {
+        return self.center0 + time * (self.center1 - self.center0);
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        return hit_sphere(
+            self.center(ray.time),
+            self.radius,
+            &self.material,
+            ray,
+            t_min,
+            t_max,
+            rec,
+        );
+    }
+
+    /// Covers the whole path, so the BVH finds the sphere at any time.
+    fn bounding_box(&self) -> Aabb {
+        let extent = DVec3::splat(self.radius.abs());
+        let start = Aabb::new(self.center0 - extent, self.center0 + extent);
+        let end = Aabb::new(self.center1 - extent, self.center1 + extent);
+        return Aabb::surrounding(&start, &end);
+    }
+}
+
+fn hit_sphere(
+    center: DVec3,
+    radius: f64,
+    material: &Arc<dyn Material>,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+    rec: &mut HitRecord,
+) -> bool {
+    let oc = ray.origin - center;
+    let a = ray.dir.length_squared();
+    let half_b = oc.dot(ray.dir);
+    let c = oc.length_squared() - radius * radius;
+    let discriminant = half_b * half_b - a * c;
+
+    if discriminant < 0.0 {
+        return false;
+    }
+    let sqrt = discriminant.sqrt();
+
+    // Prefer the near root, fall back to the far one, e.g. for rays starting inside.
+    let mut root = (-half_b - sqrt) / a;
+    if root < t_min || t_max < root {
+        root = (-half_b + sqrt) / a;
+        if root < t_min || t_max < root {
+            return false;
+        }
+    }
+    rec.t = root;
+    rec.p = ray.at(rec.t);
+    let outward_normal = (rec.p - center) / radius;
+    rec.set_face_normal(ray, outward_normal);
+    (rec.u, rec.v) = sphere_uv((rec.p - center) / radius.abs());
+    rec.material = Some(material.clone());
+
+    return true;
+}
+
+/// Maps a point on the unit sphere to (u, v): u is the angle around the y axis
+/// starting at -x, v goes from the south pole (0) to the north pole (1).
+fn sphere_uv(p: DVec3) -> (f64, f64) {
+    let theta = (-p.y).clamp(-1.0, 1.0).acos();
+    let phi = (-p.z).atan2(p.x) + PI;
+    return (phi / (2.0 * PI), theta / PI);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::HittableList;
+    use crate::material::Lambertian;
+    use crate::random::{random_unit_vector, Rng};
+
+    const EPSILON: f64 = 1e-9;
+
+    fn sphere(center: DVec3, radius: f64) -> Sphere {
+        return Sphere {
+            center,
+            radius,
+            material: Arc::new(Lambertian::new(DVec3::splat(0.5))),
+        };
+    }
+
+    fn empty_record() -> HitRecord {
+        return HitRecord {
+            p: DVec3::ZERO,
+            normal: DVec3::ZERO,
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: false,
+            material: None,
+        };
+    }
+
+    fn ray(origin: DVec3, dir: DVec3) -> Ray {
+        return Ray {
+            origin,
+            dir,
+            time: 0.0,
+        };
+    }
+
+    #[test]
+    fn ray_from_outside_hits_near_side() {
+        let s = sphere(DVec3::new(0.0, 0.0, -5.0), 1.0);
+        let mut rec = empty_record();
+        assert!(s.hit(
+            &ray(DVec3::ZERO, DVec3::NEG_Z),
+            0.0,
+            f64::INFINITY,
+            &mut rec
+        ));
+        assert!((rec.t - 4.0).abs() < EPSILON);
+        assert!((rec.p - DVec3::new(0.0, 0.0, -4.0)).length() < EPSILON);
+        assert!(rec.front_face);
+        assert!((rec.normal - DVec3::Z).length() < EPSILON);
+        assert!(rec.material.is_some());
+    }
+
+    #[test]
+    fn ray_from_inside_hits_far_side() {
+        let s = sphere(DVec3::ZERO, 2.0);
+        let mut rec = empty_record();
+        assert!(s.hit(&ray(DVec3::ZERO, DVec3::X), 0.001, f64::INFINITY, &mut rec));
+        assert!((rec.t - 2.0).abs() < EPSILON);
+        assert!((rec.p - DVec3::new(2.0, 0.0, 0.0)).length() < EPSILON);
+        assert!(!rec.front_face);
+        // The shading normal points back towards the ray origin.
+        assert!((rec.normal - DVec3::NEG_X).length() < EPSILON);
+    }
+
+    #[test]
+    fn near_root_clipped_by_t_min_reports_far_root() {
+        let s = sphere(DVec3::new(0.0, 0.0, -5.0), 1.0);
+        let mut rec = empty_record();
+        assert!(s.hit(
+            &ray(DVec3::ZERO, DVec3::NEG_Z),
+            4.5,
+            f64::INFINITY,
+            &mut rec
+        ));
+        assert!((rec.t - 6.0).abs() < EPSILON);
+        assert!(!rec.front_face);
+    }
+
+    #[test]
+    fn both_roots_outside_interval_miss() {
+        let s = sphere(DVec3::new(0.0, 0.0, -5.0), 1.0);
+        let mut rec = empty_record();
+        let r = ray(DVec3::ZERO, DVec3::NEG_Z);
+        assert!(!s.hit(&r, 0.0, 3.9, &mut rec));
+        assert!(!s.hit(&r, 6.1, f64::INFINITY, &mut rec));
+        // A miss leaves the record untouched.
+        assert_eq!(rec.t, 0.0);
+    }
+
+    #[test]
+    fn sphere_behind_ray_is_missed() {
+        let s = sphere(DVec3::new(0.0, 0.0, 5.0), 1.0);
+        let mut rec = empty_record();
+        assert!(!s.hit(
+            &ray(DVec3::ZERO, DVec3::NEG_Z),
+            0.0,
+            f64::INFINITY,
+            &mut rec
+        ));
+    }
+
+    #[test]
+    fn tangent_ray_touches_once() {
+        let s = sphere(DVec3::new(0.0, 1.0, -5.0), 1.0);
+        let mut rec = empty_record();
+        assert!(s.hit(
+            &ray(DVec3::ZERO, DVec3::NEG_Z),
+            0.0,
+            f64::INFINITY,
+            &mut rec
+        ));
+        assert!((rec.t - 5.0).abs() < EPSILON);
+        // The normal is perpendicular to the ray, which side it faces is arbitrary.
+        assert!((rec.normal.y.abs() - 1.0).abs() < EPSILON);
+
+        let s = sphere(DVec3::new(0.0, 1.0 + 1e-6, -5.0), 1.0);
+        assert!(!s.hit(
+            &ray(DVec3::ZERO, DVec3::NEG_Z),
+            0.0,
+            f64::INFINITY,
+            &mut rec
+        ));
+    }
+
+    #[test]
+    fn unnormalized_direction_scales_t() {
+        let s = sphere(DVec3::new(0.0, 0.0, -5.0), 1.0);
+        let mut rec = empty_record();
+        let r = ray(DVec3::ZERO, DVec3::new(0.0, 0.0, -2.0));
+        assert!(s.hit(&r, 0.0, f64::INFINITY, &mut rec));
+        assert!((rec.t - 2.0).abs() < EPSILON);
+        assert!((rec.p - DVec3::new(0.0, 0.0, -4.0)).length() < EPSILON);
+    }
+
+    #[test]
+    fn bounding_box_encloses_sphere() {
+        let b = sphere(DVec3::new(1.0, 2.0, 3.0), 0.5).bounding_box();
+        assert_eq!(b.min, DVec3::new(0.5, 1.5, 2.5));
+        assert_eq!(b.max, DVec3::new(1.5, 2.5, 3.5));
+    }
+
+    #[test]
+    fn random_hits_lie_on_surface_and_face_the_ray() {
+        let mut rng = Rng::new(13);
+        for _ in 0..1000 {
+            let center = 10.0 * DVec3::new(rng.next_f64(), rng.next_f64(), rng.next_f64());
+            let radius = 0.1 + 3.0 * rng.next_f64();
+            let s = sphere(center, radius);
+
+            // Aim from a random point at a random point inside the sphere, so the ray
+            // always crosses the surface whether it starts inside or outside.
+            let origin = center + 2.0 * radius * random_unit_vector(&mut rng) * rng.next_f64();
+            let target = center + 0.9 * radius * random_unit_vector(&mut rng) * rng.next_f64();
+            let r = ray(origin, target - origin);
+            let starts_inside = (origin - center).length() < radius;
+
+            let mut rec = empty_record();
+            assert!(s.hit(&r, 1e-9, f64::INFINITY, &mut rec));
+            assert!(rec.t > 0.0);
+            assert!(((rec.p - center).length() - radius).abs() < 1e-6 * radius.max(1.0));
+            assert!((rec.normal.length() - 1.0).abs() < 1e-9);
+            assert!(rec.normal.dot(r.dir) <= 0.0);
+            assert_eq!(rec.front_face, !starts_inside);
+        }
+    }
+
+    #[test]
+    fn list_reports_closest_hit() {
+        let list = HittableList {
+            objects: vec![
+                Box::new(sphere(DVec3::new(0.0, 0.0, -10.0), 1.0)),
+                Box::new(sphere(DVec3::new(0.0, 0.0, -3.0), 1.0)),
+                Box::new(sphere(DVec3::new(0.0, 0.0, -6.0), 1.0)),
+            ],
+        };
+        let mut rec = empty_record();
+        assert!(list.hit(
+            &ray(DVec3::ZERO, DVec3::NEG_Z),
+            0.0,
+            f64::INFINITY,
+            &mut rec
+        ));
+        assert!((rec.t - 2.0).abs() < EPSILON);
+
+        assert!(list.hit(
+            &ray(DVec3::ZERO, DVec3::NEG_Z),
+            4.5,
+            f64::INFINITY,
+            &mut rec
+        ));
+        assert!((rec.t - 5.0).abs() < EPSILON);
+
+        assert!(!list.hit(
+            &ray(DVec3::ZERO, DVec3::NEG_Z),
+            11.5,
+            f64::INFINITY,
+            &mut rec
+        ));
+        assert!(!list.hit(&ray(DVec3::ZERO, DVec3::X), 0.0, f64::INFINITY, &mut rec));
+    }
+
+    #[test]
+    fn list_does_not_depend_on_object_order_with_origin_inside() {
+        // The camera sits inside the large sphere; the small one must still win.
+        let make = |reversed: bool| {
+            let mut objects: Vec<Box<dyn Hittable>> = vec![
+                Box::new(sphere(DVec3::ZERO, 100.0)),
+                Box::new(sphere(DVec3::new(0.0, 0.0, -5.0), 1.0)),
+            ];
+            if reversed {
+                objects.reverse();
+            }
+            return HittableList { objects };
+        };
+        for reversed in [false, true] {
+            let mut rec = empty_record();
+            let r = ray(DVec3::ZERO, DVec3::NEG_Z);
+            assert!(make(reversed).hit(&r, 0.001, f64::INFINITY, &mut rec));
+            assert!((rec.t - 4.0).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn spherical_uvs_match_book_convention() {
+        let cases = [
+            (DVec3::X, 0.50, 0.50),
+            (DVec3::Y, 0.50, 1.00),
+            (DVec3::NEG_Y, 0.50, 0.00),
+            (DVec3::NEG_X, 0.00, 0.50),
+            (DVec3::Z, 0.25, 0.50),
+            (DVec3::NEG_Z, 0.75, 0.50),
+        ];
+        for (point, u, v) in cases {
+            let (got_u, got_v) = sphere_uv(point);
+            assert!((got_u - u).abs() < EPSILON, "u at {}", point);
+            assert!((got_v - v).abs() < EPSILON, "v at {}", point);
+        }
+
+        let s = sphere(DVec3::new(0.0, 0.0, -5.0), 2.0);
+        let mut rec = empty_record();
+        assert!(s.hit(
+            &ray(DVec3::ZERO, DVec3::NEG_Z),
+            0.0,
+            f64::INFINITY,
+            &mut rec
+        ));
+        assert!((rec.u - 0.25).abs() < EPSILON && (rec.v - 0.5).abs() < EPSILON);
+    }
+
+    #[test]
+    fn light_sampling_stays_inside_the_visible_cone() {
+        let s = sphere(DVec3::new(0.0, 0.0, -4.0), 2.0);
+        // Seen from the origin the sphere covers a cone with half-angle 30 degrees.
+        let expected = 1.0 / (2.0 * PI * (1.0 - 0.75f64.sqrt()));
+        assert!((s.pdf_value(DVec3::ZERO, DVec3::NEG_Z) - expected).abs() < EPSILON);
+        assert_eq!(s.pdf_value(DVec3::ZERO, DVec3::Z), 0.0);
+        assert_eq!(s.pdf_value(DVec3::new(0.0, 0.0, -4.5), DVec3::X), 0.0);
+
+        let mut rng = Rng::new(5);
+        for _ in 0..1000 {
+            let dir = s.random(DVec3::ZERO, &mut rng);
+            assert!(s.pdf_value(DVec3::ZERO, dir) > 0.0, "{} misses", dir);
+        }
+    }
+
+    #[test]
+    fn moving_sphere_is_hit_where_it_is_at_the_ray_time() {
+        let s = MovingSphere {
+            center0: DVec3::new(0.0, 0.0, -5.0),
+            center1: DVec3::new(4.0, 0.0, -5.0),
+            radius: 1.0,
+            material: Arc::new(Lambertian::new(DVec3::splat(0.5))),
+        };
+        let mut rec = empty_record();
+        let at = |time| Ray {
+            origin: DVec3::new(2.0, 0.0, 0.0),
+            dir: DVec3::NEG_Z,
+            time,
+        };
+        assert!(!s.hit(&at(0.0), 0.0, f64::INFINITY, &mut rec));
+        assert!(s.hit(&at(0.5), 0.0, f64::INFINITY, &mut rec));
+        assert!((rec.t - 4.0).abs() < EPSILON);
+        assert!((rec.normal - DVec3::Z).length() < EPSILON);
+        assert!(!s.hit(&at(1.0), 0.0, f64::INFINITY, &mut rec));
+
+        let bbox = s.bounding_box();
+        assert_eq!(bbox.min, DVec3::new(-1.0, -1.0, -6.0));
+        assert_eq!(bbox.max, DVec3::new(5.0, 1.0, -4.0));
+    }
+}
@@ -0,0 +1,166 @@
+use std::sync::Arc;
+
+use glam::DVec3;
+
+use crate::image::Image;
+use crate::perlin::Perlin;
+
+/// Color that varies over a surface.
+pub trait Texture: Send + Sync {
+    /// Color at surface coordinates (u, v) and world position `p` of a hit.
+    fn value(&self, u: f64, v: f64, p: DVec3) -> DVec3;
+}
+
+/// The same color everywhere.
+pub struct SolidColor {
+    pub color: DVec3,
+}
+
+/// Solid 3D checkerboard of cubes with edge length `scale`, alternating between
+/// two textures. Being defined in space, it needs no surface coordinates.
+pub struct Checker {
+    pub scale: f64,
+    pub even: Arc<dyn Texture>,
+    pub odd: Arc<dyn Texture>,
+}
+
+/// Image stretched over the (u, v) unit square, with v = 0 at the bottom row.
+pub struct ImageTexture {
+    pub image: Image,
+}
+
+/// How a [`NoiseTexture`] turns Perlin noise into a gray level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoiseKind {
+    /// Smooth noise.
+    Perlin,
+    /// Several octaves of noise, like clouds.
+    Turbulence,
+    /// Sine stripes along z distorted by turbulence.
+    Marble,
+}
+
+/// Gray procedural texture; `scale` is the spatial frequency of the noise.
+pub struct NoiseTexture {
+    pub noise: Perlin,
+    pub scale: f64,
+    pub kind: NoiseKind,
+}
+
+/// Octaves summed for turbulence-based noise.
+const TURBULENCE_DEPTH: u32 = 7;
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: DVec3) -> DVec3 {
+        return self.color;
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f64, v: f64, p: DVec3) -> DVec3 {
+        let cell = (p / self.scale).floor();
+        let parity = (cell.x + cell.y + cell.z) as i64;
+        return if parity.rem_euclid(2) == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        };
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: DVec3) -> DVec3 {
+        if self.image.width == 0 || self.image.height == 0 {
+            return DVec3::new(0.0, 1.0, 1.0);
+        }
+        let u = u.clamp(0.0, 1.0);
+        let v = 1.0 - v.clamp(0.0, 1.0);
+        let i = ((u * self.image.width as f64) as usize).min(self.image.width - 1);
+        let j = ((v * self.image.height as f64) as usize).min(self.image.height - 1);
+        return self.image.get(i, j);
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, p: DVec3) -> DVec3 {
+        let p = self.scale * p;
+        let gray = match self.kind {
+            NoiseKind::Perlin => 0.5 * (1.0 + self.noise.noise(p)),
+            NoiseKind::Turbulence => self.noise.turbulence(p, TURBULENCE_DEPTH),
+            NoiseKind::Marble => {
+                0.5 * (1.0 + (p.z + 10.0 * self.noise.turbulence(p, TURBULENCE_DEPTH)).sin())
+            }
+        };
+        return DVec3::splat(gray);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::Rng;
+
+    fn solid(color: DVec3) -> Arc<dyn Texture> {
+        return Arc::new(SolidColor { color });
+    }
+
+    #[test]
+    fn checker_alternates_in_all_three_axes() {
+        let checker = Checker {
+            scale: 0.5,
+            even: solid(DVec3::ONE),
+            odd: solid(DVec3::ZERO),
+        };
+        assert_eq!(checker.value(0.0, 0.0, DVec3::splat(0.25)), DVec3::ONE);
+        assert_eq!(
+            checker.value(0.0, 0.0, DVec3::new(0.75, 0.25, 0.25)),
+            DVec3::ZERO
+        );
+        assert_eq!(
+            checker.value(0.0, 0.0, DVec3::new(0.25, -0.25, 0.25)),
+            DVec3::ZERO
+        );
+        assert_eq!(
+            checker.value(0.0, 0.0, DVec3::new(0.25, 0.25, 0.75)),
+            DVec3::ZERO
+        );
+        assert_eq!(
+            checker.value(0.0, 0.0, DVec3::new(-0.25, -0.25, 0.25)),
+            DVec3::ONE
+        );
+    }
+
+    #[test]
+    fn image_texture_maps_v_upwards() {
+        let mut image = Image::new(2, 2);
+        image.set(0, 0, DVec3::X);
+        image.set(1, 1, DVec3::Y);
+        let texture = ImageTexture { image };
+        assert_eq!(texture.value(0.1, 0.9, DVec3::ZERO), DVec3::X);
+        assert_eq!(texture.value(0.9, 0.1, DVec3::ZERO), DVec3::Y);
+        assert_eq!(texture.value(1.0, 0.0, DVec3::ZERO), DVec3::Y);
+        assert_eq!(texture.value(-3.0, 7.0, DVec3::ZERO), DVec3::X);
+    }
+
+    #[test]
+    fn noise_is_deterministic_and_bounded() {
+        let make = |kind| NoiseTexture {
+            noise: Perlin::new(&mut Rng::new(7)),
+            scale: 4.0,
+            kind,
+        };
+        for kind in [NoiseKind::Perlin, NoiseKind::Turbulence, NoiseKind::Marble] {
+            let (a, b) = (make(kind), make(kind));
+            let mut rng = Rng::new(1);
+            for _ in 0..100 {
+                let p = DVec3::new(rng.next_f64(), rng.next_f64(), rng.next_f64()) * 10.0;
+                let value = a.value(0.0, 0.0, p);
+                assert_eq!(value, b.value(0.0, 0.0, p));
+                assert!(value.x >= 0.0 && value.x.is_finite());
+                if kind != NoiseKind::Turbulence {
+                    assert!(value.x <= 1.0);
+                }
+            }
+        }
+    }
+}
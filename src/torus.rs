@@ -0,0 +1,248 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use glam::DVec3;
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::ray::Ray;
+
+/// Ring torus around `axis`. u is the angle around the axis, v the angle around the
+/// tube, both mapped to [0, 1].
+pub struct Torus {
+    pub center: DVec3,
+    pub axis: DVec3,
+    /// Distance from the center to the middle of the tube.
+    pub major_radius: f64,
+    /// Radius of the tube.
+    pub minor_radius: f64,
+    pub material: Arc<dyn Material>,
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let big_r = self.major_radius;
+        let small_r = self.minor_radius;
+        let onb = Onb::from_w(self.axis);
+
+        // Solve with a unit direction in the torus frame, starting the ray close to the
+        // bounding sphere to keep the quartic coefficients well conditioned.
+        let dir_length = ray.dir.length();
+        let d = onb.to_local(ray.dir) / dir_length;
+        let o = onb.to_local(ray.origin - self.center);
+        let bound = big_r + small_r;
+        let half_b = o.dot(d);
+        let discriminant = half_b * half_b - (o.length_squared() - bound * bound);
+        if discriminant < 0.0 {
+            return false;
+        }
+        let offset = (-half_b - discriminant.sqrt()).max(0.0);
+        let o = o + offset * d;
+
+        // (|p|² + R² - r²)² = 4R² (x² + y²) expanded in the ray parameter.
+        let f = o.dot(d);
+        let g = o.length_squared() + big_r * big_r - small_r * small_r;
+        let four_r2 = 4.0 * big_r * big_r;
+        let coefficients = [
+            g * g - four_r2 * (o.x * o.x + o.y * o.y),
+            4.0 * f * g - 2.0 * four_r2 * (o.x * d.x + o.y * d.y),
+            4.0 * f * f + 2.0 * g - four_r2 * (d.x * d.x + d.y * d.y),
+            4.0 * f,
+        ];
+
+        let mut closest: Option<f64> = None;
+        for root in solve_quartic(coefficients) {
+            let t = (root + offset) / dir_length;
+            if t_min <= t && t <= t_max && closest.is_none_or(|best| t < best) {
+                closest = Some(t);
+            }
+        }
+        let Some(t) = closest else {
+            return false;
+        };
+
+        rec.t = t;
+        rec.p = ray.at(t);
+        let local = onb.to_local(rec.p - self.center);
+        let ring_distance = (local.x * local.x + local.y * local.y).sqrt();
+        let ring_point = DVec3::new(local.x, local.y, 0.0) * (big_r / ring_distance);
+        let local_normal = (local - ring_point) / small_r;
+        rec.set_face_normal(ray, onb.to_world(local_normal).normalize());
+        rec.u = local.y.atan2(local.x) / (2.0 * PI) + 0.5;
+        rec.v = local.z.atan2(ring_distance - big_r) / (2.0 * PI) + 0.5;
+        rec.material = Some(self.material.clone());
+        return true;
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let extent = DVec3::splat(self.major_radius + self.minor_radius);
+        return Aabb::new(self.center - extent, self.center + extent);
+    }
+}
+
+/// Real roots of t⁴ + c[3] t³ + c[2] t² + c[1] t + c[0] using Ferrari's method,
+/// refined with a few Newton steps.
+fn solve_quartic(c: [f64; 4]) -> Vec<f64> {
+    let [c0, c1, c2, c3] = c;
+    // Depress with t = y - c3 / 4 to y⁴ + p y² + q y + r.
+    let shift = c3 / 4.0;
+    let shift2 = shift * shift;
+    let p = c2 - 6.0 * shift2;
+    let q = c1 - 2.0 * c2 * shift + 8.0 * shift2 * shift;
+    let r = c0 - c1 * shift + c2 * shift2 - 3.0 * shift2 * shift2;
+
+    let mut roots = Vec::with_capacity(4);
+    if q.abs() < 1e-12 {
+        // Biquadratic: solve for y².
+        for z in solve_quadratic(1.0, p, r) {
+            if z >= 0.0 {
+                let y = z.sqrt();
+                roots.push(y);
+                roots.push(-y);
+            }
+        }
+    } else {
+        // Any positive root of the resolvent cubic splits the quartic into quadratics.
+        let m = solve_cubic(p, p * p / 4.0 - r, -q * q / 8.0)
+            .into_iter()
+            .fold(f64::NEG_INFINITY, f64::max);
+        if m <= 0.0 {
+            return roots;
+        }
+        let s = (2.0 * m).sqrt();
+        roots.extend(solve_quadratic(1.0, -s, p / 2.0 + m + q / (2.0 * s)));
+        roots.extend(solve_quadratic(1.0, s, p / 2.0 + m - q / (2.0 * s)));
+    }
+
+    let evaluate = |t: f64| {
+        let value = (((t + c3) * t + c2) * t + c1) * t + c0;
+        let derivative = ((4.0 * t + 3.0 * c3) * t + 2.0 * c2) * t + c1;
+        return (value, derivative);
+    };
+    for root in roots.iter_mut() {
+        *root -= shift;
+        for _ in 0..3 {
+            let (value, derivative) = evaluate(*root);
+            if derivative == 0.0 {
+                break;
+            }
+            *root -= value / derivative;
+        }
+    }
+    return roots;
+}
+
+/// Real roots of m³ + a m² + b m + c.
+fn solve_cubic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    // Depress with m = x - a / 3 to x³ + p x + q.
+    let shift = a / 3.0;
+    let p = b - a * shift;
+    let q = 2.0 * shift * shift * shift - b * shift + c;
+    let half_q = q / 2.0;
+    let third_p = p / 3.0;
+    let discriminant = half_q * half_q + third_p * third_p * third_p;
+
+    if discriminant > 0.0 {
+        let sqrt = discriminant.sqrt();
+        return vec![(-half_q + sqrt).cbrt() + (-half_q - sqrt).cbrt() - shift];
+    }
+    if third_p == 0.0 {
+        return vec![-shift];
+    }
+    let radius = 2.0 * (-third_p).sqrt();
+    let angle = (-half_q / (-third_p).powf(1.5)).clamp(-1.0, 1.0).acos() / 3.0;
+    return (0..3)
+        .map(|k| radius * (angle - 2.0 * PI * k as f64 / 3.0).cos() - shift)
+        .collect();
+}
+
+fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return Vec::new();
+    }
+    // Avoids cancellation between -b and the square root.
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    if q == 0.0 {
+        return vec![0.0];
+    }
+    return vec![q / a, c / q];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    fn record() -> HitRecord {
+        return HitRecord {
+            p: DVec3::ZERO,
+            normal: DVec3::ZERO,
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: false,
+            material: None,
+        };
+    }
+
+    fn torus() -> Torus {
+        return Torus {
+            center: DVec3::ZERO,
+            axis: DVec3::Y,
+            major_radius: 2.0,
+            minor_radius: 0.5,
+            material: Arc::new(Lambertian::new(DVec3::splat(0.5))),
+        };
+    }
+
+    #[test]
+    fn hits_outer_tube_and_misses_hole() {
+        let mut rec = record();
+        let through_tube = Ray {
+            origin: DVec3::new(0.0, 0.0, 10.0),
+            dir: DVec3::NEG_Z,
+            time: 0.0,
+        };
+        assert!(torus().hit(&through_tube, 0.0, f64::INFINITY, &mut rec));
+        assert!((rec.t - 7.5).abs() < 1e-7);
+        assert!((rec.normal - DVec3::Z).length() < 1e-7);
+
+        let through_hole = Ray {
+            origin: DVec3::new(0.0, 10.0, 0.0),
+            dir: DVec3::NEG_Y,
+            time: 0.0,
+        };
+        assert!(!torus().hit(&through_hole, 0.0, f64::INFINITY, &mut rec));
+    }
+
+    #[test]
+    fn hits_top_of_tube_and_respects_t_min() {
+        let mut rec = record();
+        let down = Ray {
+            origin: DVec3::new(2.0, 5.0, 0.0),
+            dir: DVec3::new(0.0, -2.0, 0.0),
+            time: 0.0,
+        };
+        assert!(torus().hit(&down, 0.0, f64::INFINITY, &mut rec));
+        assert!((rec.t - 2.25).abs() < 1e-7);
+        assert!((rec.normal - DVec3::Y).length() < 1e-7);
+
+        assert!(torus().hit(&down, 2.3, f64::INFINITY, &mut rec));
+        assert!((rec.t - 2.75).abs() < 1e-7);
+        assert!(!rec.front_face);
+    }
+
+    #[test]
+    fn quartic_roots_are_found() {
+        // (t - 1)(t - 2)(t - 3)(t - 4) = t⁴ - 10t³ + 35t² - 50t + 24
+        let mut roots = solve_quartic([24.0, -50.0, 35.0, -10.0]);
+        roots.sort_by(f64::total_cmp);
+        assert_eq!(roots.len(), 4);
+        for (root, expected) in roots.iter().zip([1.0, 2.0, 3.0, 4.0]) {
+            assert!((root - expected).abs() < 1e-9, "{:?}", roots);
+        }
+    }
+}
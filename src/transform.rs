@@ -0,0 +1,345 @@
+use std::sync::Arc;
+
+use glam::{DMat4, DQuat, DVec3};
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::ray::Ray;
+
+/// Places a shared object in the world with an affine transform. Several transforms
+/// can reference the same object, which is how meshes are instanced.
+pub struct Transform {
+    object: Arc<dyn Hittable>,
+    /// World space to object space.
+    inverse: DMat4,
+    /// Transpose of `inverse`, which maps object space normals to world space.
+    normal_matrix: DMat4,
+    bbox: Aabb,
+}
+
+impl Transform {
+    /// Wraps `object` with the object-to-world `matrix`. Panics if the matrix cannot
+    /// be inverted.
+    pub fn new(object: Arc<dyn Hittable>, matrix: DMat4) -> Transform {
+        assert!(
+            matrix.determinant() != 0.0,
+            "transform matrix must be invertible"
+        );
+        let inverse = matrix.inverse();
+        let bbox = transform_box(&object.bounding_box(), &matrix);
+        return Transform {
+            object,
+            inverse,
+            normal_matrix: inverse.transpose(),
+            bbox,
+        };
+    }
+}
+
+impl Hittable for Transform {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        return hit_transformed(
+            self.object.as_ref(),
+            &self.inverse,
+            &self.normal_matrix,
+            ray,
+            t_min,
+            t_max,
+            rec,
+        );
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        return self.bbox;
+    }
+}
+
+/// Scale, rotation and translation, applied in that order. Unlike a matrix, this
+/// can be interpolated without shearing the object.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe {
+    pub scale: DVec3,
+    pub rotation: DQuat,
+    pub translation: DVec3,
+}
+
+impl Keyframe {
+    pub fn matrix(&self) -> DMat4 {
+        return DMat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation);
+    }
+
+    /// Inverse of [`Keyframe::matrix`], built from the inverted parts without a
+    /// general matrix inversion.
+    pub fn inverse_matrix(&self) -> DMat4 {
+        return DMat4::from_scale(self.scale.recip())
+            * DMat4::from_quat(self.rotation.inverse())
+            * DMat4::from_translation(-self.translation);
+    }
+
+    /// Blend towards `other`, with `t` = 0 giving `self` and 1 giving `other`.
+    pub fn lerp(&self, other: &Keyframe, t: f64) -> Keyframe {
+        return Keyframe {
+            scale: self.scale.lerp(other.scale, t),
+            rotation: self.rotation.slerp(other.rotation, t),
+            translation: self.translation.lerp(other.translation, t),
+        };
+    }
+}
+
+/// Like [`Transform`], but moving from `start` at time 0 to `end` at time 1. Rays
+/// see the object where it is at their time, which gives motion blur.
+pub struct AnimatedTransform {
+    object: Arc<dyn Hittable>,
+    start: Keyframe,
+    end: Keyframe,
+    bbox: Aabb,
+}
+
+impl AnimatedTransform {
+    /// Panics if a scale factor is zero or changes sign between the keyframes, as
+    /// the object would be flattened at some point in between.
+    pub fn new(object: Arc<dyn Hittable>, start: Keyframe, end: Keyframe) -> AnimatedTransform {
+        assert!(
+            (start.scale * end.scale).cmpgt(DVec3::ZERO).all(),
+            "scale factors must be non-zero and keep their sign"
+        );
+        let object_box = object.bounding_box();
+        let bbox = if start.rotation == end.rotation {
+            // Every point then moves along a straight line, so the ends bound it.
+            Aabb::surrounding(
+                &transform_box(&object_box, &start.matrix()),
+                &transform_box(&object_box, &end.matrix()),
+            )
+        } else {
+            // Rotating points stay within their distance from the pivot, scaled by
+            // the largest factor.
+            let corner = object_box.min.abs().max(object_box.max.abs());
+            let largest_scale = start.scale.abs().max(end.scale.abs()).max_element();
+            let extent = DVec3::splat(largest_scale * corner.length());
+            Aabb::surrounding(
+                &Aabb::new(start.translation - extent, start.translation + extent),
+                &Aabb::new(end.translation - extent, end.translation + extent),
+            )
+        };
+        return AnimatedTransform {
+            object,
+            start,
+            end,
+            bbox,
+        };
+    }
+}
+
+impl Hittable for AnimatedTransform {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let inverse = self.start.lerp(&self.end, ray.time).inverse_matrix();
+        return hit_transformed(
+            self.object.as_ref(),
+            &inverse,
+            &inverse.transpose(),
+            ray,
+            t_min,
+            t_max,
+            rec,
+        );
+    }
+
+    /// Covers the whole motion, so the BVH finds the object at any time.
+    fn bounding_box(&self) -> Aabb {
+        return self.bbox;
+    }
+}
+
+fn hit_transformed(
+    object: &dyn Hittable,
+    inverse: &DMat4,
+    normal_matrix: &DMat4,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+    rec: &mut HitRecord,
+) -> bool {
+    // The direction is not renormalized, so t means the same in both spaces.
+    let local_ray = Ray {
+        origin: inverse.transform_point3(ray.origin),
+        dir: inverse.transform_vector3(ray.dir),
+        time: ray.time,
+    };
+    if !object.hit(&local_ray, t_min, t_max, rec) {
+        return false;
+    }
+    rec.p = ray.at(rec.t);
+    // The inverse transpose keeps normals perpendicular to the surface and facing
+    // against the ray, so `front_face` stays valid.
+    rec.normal = normal_matrix.transform_vector3(rec.normal).normalize();
+    return true;
+}
+
+/// Box around the eight transformed corners of `bbox`. Unbounded boxes stay unbounded.
+fn transform_box(bbox: &Aabb, matrix: &DMat4) -> Aabb {
+    if !(bbox.min.is_finite() && bbox.max.is_finite()) {
+        return Aabb::new(DVec3::NEG_INFINITY, DVec3::INFINITY);
+    }
+    let mut result = Aabb::EMPTY;
+    for corner in 0..8 {
+        let pick = |axis: usize| {
+            if corner & (1 << axis) == 0 {
+                bbox.min[axis]
+            } else {
+                bbox.max[axis]
+            }
+        };
+        let p = matrix.transform_point3(DVec3::new(pick(0), pick(1), pick(2)));
+        result = Aabb::surrounding(&result, &Aabb::new(p, p));
+    }
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::sphere::Sphere;
+    use glam::DQuat;
+
+    fn record() -> HitRecord {
+        return HitRecord {
+            p: DVec3::ZERO,
+            normal: DVec3::ZERO,
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: false,
+            material: None,
+        };
+    }
+
+    fn unit_sphere() -> Arc<dyn Hittable> {
+        return Arc::new(Sphere {
+            center: DVec3::ZERO,
+            radius: 1.0,
+            material: Arc::new(Lambertian::new(DVec3::splat(0.5))),
+        });
+    }
+
+    #[test]
+    fn translated_instances_share_one_object() {
+        let sphere = unit_sphere();
+        let left = Transform::new(
+            sphere.clone(),
+            DMat4::from_translation(DVec3::new(-3.0, 0.0, 0.0)),
+        );
+        let right = Transform::new(sphere, DMat4::from_translation(DVec3::new(3.0, 0.0, 0.0)));
+        let ray = Ray {
+            origin: DVec3::new(3.0, 0.0, 5.0),
+            dir: DVec3::NEG_Z,
+            time: 0.0,
+        };
+        let mut rec = record();
+        assert!(!left.hit(&ray, 0.0, f64::INFINITY, &mut rec));
+        assert!(right.hit(&ray, 0.0, f64::INFINITY, &mut rec));
+        assert!((rec.t - 4.0).abs() < 1e-12);
+        assert!((rec.p - DVec3::new(3.0, 0.0, 1.0)).length() < 1e-12);
+        assert!(rec.front_face);
+    }
+
+    #[test]
+    fn non_uniform_scale_transforms_normals() {
+        // Ellipsoid with semi-axes 2, 1, 1; at (sqrt(2), sqrt(0.5), 0) the outward
+        // normal is proportional to (x / 4, y, 0).
+        let ellipsoid = Transform::new(unit_sphere(), DMat4::from_scale(DVec3::new(2.0, 1.0, 1.0)));
+        let target = DVec3::new(2f64.sqrt(), 0.5f64.sqrt(), 0.0);
+        let ray = Ray {
+            origin: target + DVec3::new(5.0, 0.0, 0.0),
+            dir: DVec3::new(-2.0, 0.0, 0.0),
+            time: 0.0,
+        };
+        let mut rec = record();
+        assert!(ellipsoid.hit(&ray, 0.0, f64::INFINITY, &mut rec));
+        let expected = DVec3::new(target.x / 4.0, target.y, 0.0).normalize();
+        assert!((rec.p - target).length() < 1e-9);
+        assert!((rec.normal - expected).length() < 1e-9);
+    }
+
+    #[test]
+    fn bounding_box_covers_rotated_object() {
+        let rotation = DMat4::from_scale_rotation_translation(
+            DVec3::new(1.0, 1.0, 3.0),
+            DQuat::from_rotation_y(std::f64::consts::FRAC_PI_2),
+            DVec3::new(0.0, 1.0, 0.0),
+        );
+        let bbox = Transform::new(unit_sphere(), rotation).bounding_box();
+        assert!((bbox.min - DVec3::new(-3.0, 0.0, -1.0)).length() < 1e-9);
+        assert!((bbox.max - DVec3::new(3.0, 2.0, 1.0)).length() < 1e-9);
+    }
+
+    #[test]
+    fn animated_transform_moves_with_ray_time() {
+        let start = Keyframe {
+            scale: DVec3::ONE,
+            rotation: DQuat::IDENTITY,
+            translation: DVec3::new(0.0, 0.0, -5.0),
+        };
+        let end = Keyframe {
+            translation: DVec3::new(4.0, 0.0, -5.0),
+            ..start
+        };
+        let moving = AnimatedTransform::new(unit_sphere(), start, end);
+        let at = |time| Ray {
+            origin: DVec3::new(2.0, 0.0, 0.0),
+            dir: DVec3::NEG_Z,
+            time,
+        };
+        let mut rec = record();
+        assert!(!moving.hit(&at(0.0), 0.0, f64::INFINITY, &mut rec));
+        assert!(moving.hit(&at(0.5), 0.0, f64::INFINITY, &mut rec));
+        assert!((rec.t - 4.0).abs() < 1e-12);
+        assert!(!moving.hit(&at(1.0), 0.0, f64::INFINITY, &mut rec));
+        assert_eq!(moving.bounding_box().min, DVec3::new(-1.0, -1.0, -6.0));
+        assert_eq!(moving.bounding_box().max, DVec3::new(5.0, 1.0, -4.0));
+    }
+
+    #[test]
+    fn rotating_bounding_box_contains_every_moment() {
+        let cube: Arc<dyn Hittable> = Arc::new(crate::cuboid::Cuboid {
+            min: DVec3::new(1.0, -0.5, -0.5),
+            max: DVec3::new(3.0, 0.5, 0.5),
+            material: Arc::new(Lambertian::new(DVec3::splat(0.5))),
+        });
+        let start = Keyframe {
+            scale: DVec3::ONE,
+            rotation: DQuat::IDENTITY,
+            translation: DVec3::ZERO,
+        };
+        let end = Keyframe {
+            scale: DVec3::splat(2.0),
+            rotation: DQuat::from_rotation_y(3.0),
+            translation: DVec3::new(0.0, 1.0, 0.0),
+        };
+        let bbox = AnimatedTransform::new(cube.clone(), start, end).bounding_box();
+        for step in 0..=20 {
+            let matrix = start.lerp(&end, step as f64 / 20.0).matrix();
+            let moment = transform_box(&cube.bounding_box(), &matrix);
+            assert!(bbox.min.cmple(moment.min).all() && bbox.max.cmpge(moment.max).all());
+        }
+    }
+
+    #[test]
+    fn keyframe_inverse_matches_matrix_inverse() {
+        let start = Keyframe {
+            scale: DVec3::new(2.0, -0.5, 3.0),
+            rotation: DQuat::from_axis_angle(DVec3::new(1.0, 2.0, 3.0).normalize(), 0.7),
+            translation: DVec3::new(4.0, -1.0, 2.0),
+        };
+        let end = Keyframe {
+            scale: DVec3::new(1.0, -2.0, 0.5),
+            rotation: DQuat::from_rotation_z(-2.0),
+            translation: DVec3::ZERO,
+        };
+        for t in [0.0, 0.3, 1.0] {
+            let keyframe = start.lerp(&end, t);
+            let expected = keyframe.matrix().inverse();
+            assert!(keyframe.inverse_matrix().abs_diff_eq(expected, 1e-12));
+        }
+    }
+}
@@ -0,0 +1,392 @@
+use std::sync::Arc;
+
+use glam::{DVec2, DVec3};
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+
+/// Single triangle. Without per-vertex normals it is flat shaded; without UVs the
+/// barycentric coordinates of the hit are used as (u, v).
+pub struct Triangle {
+    /// Counter-clockwise vertices when seen from the front.
+    pub vertices: [DVec3; 3],
+    pub normals: Option<[DVec3; 3]>,
+    pub uvs: Option<[DVec2; 3]>,
+    pub material: Arc<dyn Material>,
+}
+
+/// Indexed triangle mesh sharing its vertex data between all faces.
+pub struct TriangleMesh {
+    pub positions: Vec<DVec3>,
+    /// Either empty or one normal per position.
+    pub normals: Vec<DVec3>,
+    /// Either empty or one texture coordinate per position.
+    pub uvs: Vec<DVec2>,
+    /// Vertex indices of each face, counter-clockwise when seen from the front.
+    pub indices: Vec<[usize; 3]>,
+    pub material: Arc<dyn Material>,
+}
+
+/// One face of a [`TriangleMesh`].
+pub struct MeshTriangle {
+    mesh: Arc<TriangleMesh>,
+    face: usize,
+}
+
+impl TriangleMesh {
+    /// Checks that every index and attribute array is consistent with `positions`.
+    pub fn validate(&self) -> Result<(), String> {
+        let count = self.positions.len();
+        if !self.normals.is_empty() && self.normals.len() != count {
+            return Err(format!(
+                "{} normals for {} positions",
+                self.normals.len(),
+                count
+            ));
+        }
+        if !self.uvs.is_empty() && self.uvs.len() != count {
+            return Err(format!("{} uvs for {} positions", self.uvs.len(), count));
+        }
+        if let Some(face) = self
+            .indices
+            .iter()
+            .position(|f| f.iter().any(|&i| i >= count))
+        {
+            return Err(format!("face {} references a missing vertex", face));
+        }
+        return Ok(());
+    }
+
+    /// Splits the mesh into one object per face, e.g. to put them into a BVH.
+    pub fn triangles(mesh: &Arc<TriangleMesh>) -> Vec<Box<dyn Hittable>> {
+        return (0..mesh.indices.len())
+            .map(|face| {
+                Box::new(MeshTriangle {
+                    mesh: mesh.clone(),
+                    face,
+                }) as Box<dyn Hittable>
+            })
+            .collect();
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let Some(hit) = intersect(ray, &self.vertices, t_min, t_max) else {
+            return false;
+        };
+        fill_record(
+            ray,
+            rec,
+            hit,
+            &self.vertices,
+            self.normals.as_ref(),
+            self.uvs.as_ref(),
+        );
+        rec.material = Some(self.material.clone());
+        return true;
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        return bounds(&self.vertices);
+    }
+}
+
+impl MeshTriangle {
+    fn vertices(&self) -> [DVec3; 3] {
+        let [a, b, c] = self.mesh.indices[self.face];
+        return [
+            self.mesh.positions[a],
+            self.mesh.positions[b],
+            self.mesh.positions[c],
+        ];
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let vertices = self.vertices();
+        let Some(hit) = intersect(ray, &vertices, t_min, t_max) else {
+            return false;
+        };
+        let [a, b, c] = self.mesh.indices[self.face];
+        let normals = if self.mesh.normals.is_empty() {
+            None
+        } else {
+            Some([
+                self.mesh.normals[a],
+                self.mesh.normals[b],
+                self.mesh.normals[c],
+            ])
+        };
+        let uvs = if self.mesh.uvs.is_empty() {
+            None
+        } else {
+            Some([self.mesh.uvs[a], self.mesh.uvs[b], self.mesh.uvs[c]])
+        };
+        fill_record(ray, rec, hit, &vertices, normals.as_ref(), uvs.as_ref());
+        rec.material = Some(self.mesh.material.clone());
+        return true;
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        return bounds(&self.vertices());
+    }
+}
+
+/// Möller–Trumbore intersection. Returns the ray parameter and the barycentric
+/// weights of the second and third vertex.
+fn intersect(ray: &Ray, vertices: &[DVec3; 3], t_min: f64, t_max: f64) -> Option<(f64, f64, f64)> {
+    let edge1 = vertices[1] - vertices[0];
+    let edge2 = vertices[2] - vertices[0];
+    let pvec = ray.dir.cross(edge2);
+    let det = edge1.dot(pvec);
+    // Ray parallel to the triangle plane, relative to the edge and direction
+    // lengths so that tiny triangles are not mistaken for parallel ones.
+    if det.abs() <= 1e-12 * edge1.length() * edge2.length() * ray.dir.length() {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let tvec = ray.origin - vertices[0];
+    let b1 = tvec.dot(pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let qvec = tvec.cross(edge1);
+    let b2 = ray.dir.dot(qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(qvec) * inv_det;
+    if t < t_min || t_max < t {
+        return None;
+    }
+    return Some((t, b1, b2));
+}
+
+fn fill_record(
+    ray: &Ray,
+    rec: &mut HitRecord,
+    (t, b1, b2): (f64, f64, f64),
+    vertices: &[DVec3; 3],
+    normals: Option<&[DVec3; 3]>,
+    uvs: Option<&[DVec2; 3]>,
+) {
+    let b0 = 1.0 - b1 - b2;
+    rec.t = t;
+    rec.p = ray.at(t);
+
+    let geometric_normal = (vertices[1] - vertices[0])
+        .cross(vertices[2] - vertices[0])
+        .normalize();
+    rec.set_face_normal(ray, geometric_normal);
+    if let Some(n) = normals {
+        // Interpolated shading normal, kept on the same side as the geometric one
+        // even when the vertex normals disagree with the winding.
+        let shading_normal = (b0 * n[0] + b1 * n[1] + b2 * n[2]).normalize_or_zero();
+        if shading_normal != DVec3::ZERO {
+            rec.normal = if shading_normal.dot(rec.normal) < 0.0 {
+                -shading_normal
+            } else {
+                shading_normal
+            };
+        }
+    }
+
+    let uv = match uvs {
+        Some(uv) => b0 * uv[0] + b1 * uv[1] + b2 * uv[2],
+        None => DVec2::new(b1, b2),
+    };
+    rec.u = uv.x;
+    rec.v = uv.y;
+}
+
+fn bounds(vertices: &[DVec3; 3]) -> Aabb {
+    return Aabb::new(
+        vertices[0].min(vertices[1]).min(vertices[2]),
+        vertices[0].max(vertices[1]).max(vertices[2]),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    const EPSILON: f64 = 1e-9;
+
+    fn material() -> Arc<dyn Material> {
+        return Arc::new(Lambertian::new(DVec3::splat(0.5)));
+    }
+
+    fn record() -> HitRecord {
+        return HitRecord {
+            p: DVec3::ZERO,
+            normal: DVec3::ZERO,
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: false,
+            material: None,
+        };
+    }
+
+    /// Unit right triangle in the z = -1 plane, facing +z.
+    fn triangle() -> Triangle {
+        return Triangle {
+            vertices: [
+                DVec3::new(0.0, 0.0, -1.0),
+                DVec3::new(1.0, 0.0, -1.0),
+                DVec3::new(0.0, 1.0, -1.0),
+            ],
+            normals: None,
+            uvs: None,
+            material: material(),
+        };
+    }
+
+    fn ray_towards(x: f64, y: f64) -> Ray {
+        return Ray {
+            origin: DVec3::new(x, y, 0.0),
+            dir: DVec3::NEG_Z,
+            time: 0.0,
+        };
+    }
+
+    #[test]
+    fn front_hit_reports_barycentric_uv() {
+        let mut rec = record();
+        assert!(triangle().hit(&ray_towards(0.25, 0.5), 0.0, f64::INFINITY, &mut rec));
+        assert!((rec.t - 1.0).abs() < EPSILON);
+        assert!(rec.front_face);
+        assert!((rec.normal - DVec3::Z).length() < EPSILON);
+        assert!((rec.u - 0.25).abs() < EPSILON);
+        assert!((rec.v - 0.5).abs() < EPSILON);
+    }
+
+    #[test]
+    fn back_hit_flips_normal() {
+        let mut rec = record();
+        let ray = Ray {
+            origin: DVec3::new(0.25, 0.25, -2.0),
+            dir: DVec3::Z,
+            time: 0.0,
+        };
+        assert!(triangle().hit(&ray, 0.0, f64::INFINITY, &mut rec));
+        assert!(!rec.front_face);
+        assert!((rec.normal - DVec3::NEG_Z).length() < EPSILON);
+    }
+
+    #[test]
+    fn misses_outside_edges_parallel_rays_and_clipped_hits() {
+        let mut rec = record();
+        let tri = triangle();
+        assert!(!tri.hit(&ray_towards(0.6, 0.6), 0.0, f64::INFINITY, &mut rec));
+        assert!(!tri.hit(&ray_towards(-0.1, 0.5), 0.0, f64::INFINITY, &mut rec));
+        assert!(!tri.hit(&ray_towards(0.25, 0.25), 0.0, 0.5, &mut rec));
+        let parallel = Ray {
+            origin: DVec3::new(-1.0, 0.25, -1.0),
+            dir: DVec3::X,
+            time: 0.0,
+        };
+        assert!(!tri.hit(&parallel, 0.0, f64::INFINITY, &mut rec));
+    }
+
+    #[test]
+    fn interpolates_shading_normals_and_uvs() {
+        let mut tri = triangle();
+        tri.normals = Some([
+            DVec3::Z,
+            DVec3::new(1.0, 0.0, 1.0).normalize(),
+            DVec3::new(0.0, 1.0, 1.0).normalize(),
+        ]);
+        tri.uvs = Some([
+            DVec2::new(0.0, 0.0),
+            DVec2::new(2.0, 0.0),
+            DVec2::new(0.0, 4.0),
+        ]);
+        let mut rec = record();
+        assert!(tri.hit(&ray_towards(0.5, 0.0), 0.0, f64::INFINITY, &mut rec));
+        let expected = (DVec3::Z + DVec3::new(1.0, 0.0, 1.0).normalize()).normalize();
+        assert!((rec.normal - expected).length() < EPSILON);
+        assert!((rec.u - 1.0).abs() < EPSILON);
+        assert!(rec.v.abs() < EPSILON);
+    }
+
+    #[test]
+    fn shading_normals_face_the_same_side_as_the_geometric_normal() {
+        let mut tri = triangle();
+        // Vertex normals pointing against the winding.
+        tri.normals = Some([DVec3::NEG_Z; 3]);
+        let mut rec = record();
+        assert!(tri.hit(&ray_towards(0.25, 0.25), 0.0, f64::INFINITY, &mut rec));
+        assert!(rec.front_face);
+        assert!((rec.normal - DVec3::Z).length() < EPSILON);
+
+        let from_behind = Ray {
+            origin: DVec3::new(0.25, 0.25, -2.0),
+            dir: DVec3::Z,
+            time: 0.0,
+        };
+        assert!(tri.hit(&from_behind, 0.0, f64::INFINITY, &mut rec));
+        assert!(!rec.front_face);
+        assert!((rec.normal - DVec3::NEG_Z).length() < EPSILON);
+    }
+
+    #[test]
+    fn tiny_triangles_are_still_hit() {
+        let mut tri = triangle();
+        for vertex in tri.vertices.iter_mut() {
+            *vertex *= 1e-7;
+        }
+        let ray = Ray {
+            origin: DVec3::new(2.5e-8, 2.5e-8, 0.0),
+            dir: DVec3::NEG_Z,
+            time: 0.0,
+        };
+        let mut rec = record();
+        assert!(tri.hit(&ray, 0.0, f64::INFINITY, &mut rec));
+        assert!((rec.t - 1e-7).abs() < 1e-17);
+    }
+
+    #[test]
+    fn mesh_faces_share_vertices() {
+        let mesh = Arc::new(TriangleMesh {
+            positions: vec![
+                DVec3::new(0.0, 0.0, -1.0),
+                DVec3::new(1.0, 0.0, -1.0),
+                DVec3::new(1.0, 1.0, -1.0),
+                DVec3::new(0.0, 1.0, -1.0),
+            ],
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            indices: vec![[0, 1, 2], [0, 2, 3]],
+            material: material(),
+        });
+        assert!(mesh.validate().is_ok());
+        let faces = TriangleMesh::triangles(&mesh);
+        assert_eq!(faces.len(), 2);
+
+        let mut rec = record();
+        assert!(!faces[0].hit(&ray_towards(0.25, 0.75), 0.0, f64::INFINITY, &mut rec));
+        assert!(faces[1].hit(&ray_towards(0.25, 0.75), 0.0, f64::INFINITY, &mut rec));
+        assert!(rec.front_face);
+        assert_eq!(faces[1].bounding_box().max, DVec3::new(1.0, 1.0, -1.0));
+    }
+
+    #[test]
+    fn mesh_validation_rejects_bad_indices() {
+        let mesh = TriangleMesh {
+            positions: vec![DVec3::ZERO, DVec3::X, DVec3::Y],
+            normals: vec![DVec3::Z],
+            uvs: Vec::new(),
+            indices: vec![[0, 1, 3]],
+            material: material(),
+        };
+        assert!(mesh.validate().is_err());
+    }
+}